@@ -0,0 +1,60 @@
+use crate::inspect::{self, Inspect};
+use crate::law::Law;
+use crate::symbolic::{Expr, Symbolic, ToExpr};
+use crate::{common, Density, Graph, Rng, Uncertain};
+use std::sync::Arc;
+
+pub struct Common<U> {
+    uncertain: U,
+}
+
+impl<U> Common<U>
+where
+    U: Uncertain,
+{
+    pub fn new(uncertain: U) -> Self {
+        Self { uncertain }
+    }
+}
+
+impl<U> Uncertain for Common<U>
+where
+    U: Uncertain,
+{
+    type Value = U::Value;
+
+    fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
+        let _guard = common::share(rng);
+        self.uncertain.sample(rng, epoch)
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for Common<U>
+where
+    U: Uncertain,
+{
+    fn law(&self) -> Option<Law> {
+        inspect::law(&self.uncertain)
+    }
+
+    fn density(&self) -> Option<Arc<dyn Density + Send + Sync>> {
+        inspect::density(&self.uncertain)
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        inspect::visit(&self.uncertain, graph)
+    }
+}
+
+impl<U> ToExpr for Common<U>
+where
+    U: Symbolic,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "common_inputs")
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::{self, Inspect};
+use crate::symbolic::{Expr, ToExpr};
+use crate::{Graph, Rng, Uncertain};
 
 pub struct FlatMap<U, F> {
     uncertain: U,
@@ -28,4 +30,31 @@ where
         let v = self.uncertain.sample(rng, epoch);
         (self.func)(v).sample(rng, epoch)
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<O, U, F> Inspect for FlatMap<U, F>
+where
+    U: Uncertain,
+    O: Uncertain,
+    F: Fn(U::Value) -> O,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let input = inspect::visit(&self.uncertain, graph);
+        graph.add("flat_map", vec![input])
+    }
+}
+
+impl<O, U, F> ToExpr for FlatMap<U, F>
+where
+    U: Uncertain,
+    O: Uncertain,
+    F: Fn(U::Value) -> O,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "flat_map")
+    }
 }
@@ -1,4 +1,6 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::{self, Inspect};
+use crate::symbolic::{Expr, ToExpr};
+use crate::{Graph, Rng, Uncertain};
 
 pub struct Join<A, B, F> {
     a: A,
@@ -30,4 +32,34 @@ where
         let b = self.b.sample(rng, epoch);
         (self.func)(a, b)
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<O, A, B, F> Inspect for Join<A, B, F>
+where
+    A: Uncertain,
+    B: Uncertain,
+    F: Fn(A::Value, B::Value) -> O,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let inputs = vec![
+            inspect::visit(&self.a, graph),
+            inspect::visit(&self.b, graph),
+        ];
+        graph.add("join", inputs)
+    }
+}
+
+impl<O, A, B, F> ToExpr for Join<A, B, F>
+where
+    A: Uncertain,
+    B: Uncertain,
+    F: Fn(A::Value, B::Value) -> O,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "join")
+    }
 }
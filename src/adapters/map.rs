@@ -1,4 +1,6 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::{self, Inspect};
+use crate::symbolic::{Expr, ToExpr};
+use crate::{Graph, Rng, Uncertain};
 
 pub struct Map<U, F> {
     uncertain: U,
@@ -26,4 +28,29 @@ where
         let v = self.uncertain.sample(rng, epoch);
         (self.func)(v)
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<T, U, F> Inspect for Map<U, F>
+where
+    U: Uncertain,
+    F: Fn(U::Value) -> T,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let input = inspect::visit(&self.uncertain, graph);
+        graph.add("map", vec![input])
+    }
+}
+
+impl<T, U, F> ToExpr for Map<U, F>
+where
+    U: Uncertain,
+    F: Fn(U::Value) -> T,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "map")
+    }
 }
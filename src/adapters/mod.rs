@@ -1,9 +1,18 @@
+mod common;
 mod flat_map;
 mod join;
 mod map;
+mod named;
 mod ops;
+mod truncate;
 
+pub use common::Common;
 pub use flat_map::FlatMap;
 pub use join::Join;
 pub use map::Map;
-pub use ops::{And, Difference, Not, Or, Product, Ratio, Sum};
+pub use named::Named;
+pub use ops::{
+    Abs, And, Cos, Difference, Exp, GreaterEqual, GreaterThan, LessEqual, LessThan, Ln, Not, Or,
+    Product, Ratio, Sin, Sqrt, Sum,
+};
+pub use truncate::{Truncated, TruncationError};
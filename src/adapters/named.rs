@@ -0,0 +1,81 @@
+use crate::inspect::{self, Inspect};
+use crate::law::Law;
+use crate::symbolic::{Expr, Symbolic, ToExpr};
+use crate::{common, importance, sensitivity, Density, Graph, Rng, Uncertain};
+use std::sync::Arc;
+
+pub struct Named<U> {
+    uncertain: U,
+    name: String,
+}
+
+impl<U> Named<U>
+where
+    U: Uncertain,
+{
+    pub fn new(uncertain: U, name: &str) -> Self {
+        Self {
+            uncertain,
+            name: name.to_string(),
+        }
+    }
+
+    /// Sample the input, or draw it from its proposal when importance sampling.
+    fn draw(&self, rng: &mut Rng, epoch: usize) -> U::Value {
+        if importance::input(&self.name, || inspect::density(&self.uncertain), rng) {
+            let value = self.uncertain.sample(rng, epoch);
+            importance::finish(&self.name);
+            return value;
+        }
+        self.uncertain.sample(rng, epoch)
+    }
+}
+
+impl<U> Uncertain for Named<U>
+where
+    U: Uncertain,
+{
+    type Value = U::Value;
+
+    fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
+        // A sensitivity analysis isolates its inputs, such that it takes
+        // precedence over sharing them.
+        let input = sensitivity::input(&self.name)
+            .or_else(|| common::input(&self.name).map(|rng| (rng, epoch)));
+        match input {
+            Some((mut rng, epoch)) => self.draw(&mut rng, epoch),
+            None => self.draw(rng, epoch),
+        }
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for Named<U>
+where
+    U: Uncertain,
+{
+    fn law(&self) -> Option<Law> {
+        inspect::law(&self.uncertain)
+    }
+
+    fn density(&self) -> Option<Arc<dyn Density + Send + Sync>> {
+        inspect::density(&self.uncertain)
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let input = inspect::visit(&self.uncertain, graph);
+        graph.add(self.name.clone(), vec![input])
+    }
+}
+
+impl<U> ToExpr for Named<U>
+where
+    U: Symbolic,
+{
+    fn expr(&self) -> Expr {
+        self.uncertain.expr()
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::{self, Inspect};
+use crate::symbolic::{Comparison, Expr, Function, Symbolic, ToExpr};
+use crate::{Graph, Rng, Uncertain};
+use num_traits::Float;
 
 pub struct Not<U>
 where
@@ -28,6 +31,31 @@ where
     fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
         !self.uncertain.sample(rng, epoch).into()
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for Not<U>
+where
+    U: Uncertain,
+    U::Value: Into<bool>,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let input = inspect::visit(&self.uncertain, graph);
+        graph.add("!", vec![input])
+    }
+}
+
+impl<U> ToExpr for Not<U>
+where
+    U: Symbolic,
+    U::Value: Into<bool>,
+{
+    fn expr(&self) -> Expr {
+        Expr::Not(Box::new(self.uncertain.expr()))
+    }
 }
 
 macro_rules! logic_op {
@@ -67,6 +95,104 @@ macro_rules! logic_op {
             fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
                 self.a.sample(rng, epoch).into() $op self.b.sample(rng, epoch).into()
             }
+
+            fn inspect(&self) -> Option<&dyn Inspect> {
+                Some(self)
+            }
+        }
+
+        impl<A, B> Inspect for $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: Into<bool>,
+            B::Value: Into<bool>,
+        {
+            fn visit(&self, graph: &mut Graph) -> usize {
+                let inputs = vec![inspect::visit(&self.a, graph), inspect::visit(&self.b, graph)];
+                graph.add(stringify!($op), inputs)
+            }
+        }
+
+        impl<A, B> ToExpr for $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: Into<bool>,
+            B::Value: Into<bool>,
+        {
+            fn expr(&self) -> Expr {
+                Expr::opaque(self, stringify!($op))
+            }
+        }
+    };
+}
+
+macro_rules! compare_op {
+    ($name:ident, $op:tt, $comparison:ident) => {
+        pub struct $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: PartialOrd<B::Value>,
+        {
+            a: A,
+            b: B,
+        }
+
+        impl<A, B> $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: PartialOrd<B::Value>,
+        {
+            pub fn new(a: A, b: B) -> Self {
+                Self { a, b }
+            }
+        }
+
+        impl<A, B> Uncertain for $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: PartialOrd<B::Value>,
+        {
+            type Value = bool;
+
+            fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
+                self.a.sample(rng, epoch) $op self.b.sample(rng, epoch)
+            }
+
+            fn inspect(&self) -> Option<&dyn Inspect> {
+                Some(self)
+            }
+        }
+
+        impl<A, B> Inspect for $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: PartialOrd<B::Value>,
+        {
+            fn visit(&self, graph: &mut Graph) -> usize {
+                let inputs = vec![inspect::visit(&self.a, graph), inspect::visit(&self.b, graph)];
+                graph.add(stringify!($op), inputs)
+            }
+        }
+
+        impl<A, B> ToExpr for $name<A, B>
+        where
+            A: Symbolic,
+            B: Symbolic,
+            A::Value: PartialOrd<B::Value>,
+        {
+            fn expr(&self) -> Expr {
+                Expr::Compare(
+                    Comparison::$comparison,
+                    Box::new(self.a.expr()),
+                    Box::new(self.b.expr()),
+                )
+            }
         }
     };
 }
@@ -105,6 +231,92 @@ macro_rules! binary_op {
             fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
                 self.a.sample(rng, epoch) $op self.b.sample(rng, epoch)
             }
+
+            fn inspect(&self) -> Option<&dyn Inspect> {
+                Some(self)
+            }
+        }
+
+        impl<A, B> Inspect for $name<A, B>
+        where
+            A: Uncertain,
+            B: Uncertain,
+            A::Value: std::ops::$trait<B::Value>,
+        {
+            fn visit(&self, graph: &mut Graph) -> usize {
+                let inputs = vec![inspect::visit(&self.a, graph), inspect::visit(&self.b, graph)];
+                graph.add(stringify!($op), inputs)
+            }
+        }
+
+        impl<A, B> ToExpr for $name<A, B>
+        where
+            A: Symbolic,
+            B: Symbolic,
+            A::Value: std::ops::$trait<B::Value>,
+        {
+            fn expr(&self) -> Expr {
+                Expr::$name(Box::new(self.a.expr()), Box::new(self.b.expr()))
+            }
+        }
+    };
+}
+
+macro_rules! unary_op {
+    ($name:ident, $func:ident) => {
+        pub struct $name<U>
+        where
+            U: Uncertain,
+            U::Value: Float,
+        {
+            uncertain: U,
+        }
+
+        impl<U> $name<U>
+        where
+            U: Uncertain,
+            U::Value: Float,
+        {
+            pub fn new(uncertain: U) -> Self {
+                Self { uncertain }
+            }
+        }
+
+        impl<U> Uncertain for $name<U>
+        where
+            U: Uncertain,
+            U::Value: Float,
+        {
+            type Value = U::Value;
+
+            fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
+                self.uncertain.sample(rng, epoch).$func()
+            }
+
+            fn inspect(&self) -> Option<&dyn Inspect> {
+                Some(self)
+            }
+        }
+
+        impl<U> Inspect for $name<U>
+        where
+            U: Uncertain,
+            U::Value: Float,
+        {
+            fn visit(&self, graph: &mut Graph) -> usize {
+                let input = inspect::visit(&self.uncertain, graph);
+                graph.add(stringify!($func), vec![input])
+            }
+        }
+
+        impl<U> ToExpr for $name<U>
+        where
+            U: Symbolic,
+            U::Value: Float,
+        {
+            fn expr(&self) -> Expr {
+                Expr::Function(Function::$name, Box::new(self.uncertain.expr()))
+            }
         }
     };
 }
@@ -112,11 +324,23 @@ macro_rules! binary_op {
 logic_op!(And, &&);
 logic_op!(Or, ||);
 
+compare_op!(LessThan, <, Less);
+compare_op!(LessEqual, <=, LessEqual);
+compare_op!(GreaterThan, >, Greater);
+compare_op!(GreaterEqual, >=, GreaterEqual);
+
 binary_op!(Sum, +, Add);
 binary_op!(Difference, -, Sub);
 binary_op!(Product, *, Mul);
 binary_op!(Ratio, /, Div);
 
+unary_op!(Sqrt, sqrt);
+unary_op!(Exp, exp);
+unary_op!(Ln, ln);
+unary_op!(Sin, sin);
+unary_op!(Cos, cos);
+unary_op!(Abs, abs);
+
 #[cfg(test)]
 mod tests {
     use crate::{PointMass, Uncertain};
@@ -135,7 +359,7 @@ mod tests {
 
         let a = PointMass::new(true);
         let b = PointMass::new(false);
-        assert_eq!(a.and(b).pr(0.00001), false);
+        assert!(!a.and(b).pr(0.00001));
     }
 
     #[test]
@@ -146,7 +370,17 @@ mod tests {
 
         let a = PointMass::new(false);
         let b = PointMass::new(false);
-        assert_eq!(a.or(b).pr(0.00001), false);
+        assert!(!a.or(b).pr(0.00001));
+    }
+
+    #[test]
+    fn op_compare() {
+        let a = PointMass::new(5);
+        let b = PointMass::new(9);
+        assert!(a.lt(b).pr(0.99999));
+        assert!(a.le(a).pr(0.99999));
+        assert!(!a.gt(b).pr(0.00001));
+        assert!(!a.ge(b).pr(0.00001));
     }
 
     #[test]
@@ -176,4 +410,13 @@ mod tests {
         let b = PointMass::new(9.0);
         assert!(a.div(b).map(|sum| sum == 5.0 / 9.0).pr(0.99999));
     }
+
+    #[test]
+    fn op_functions() {
+        let a = PointMass::new(4.0_f64);
+        assert!(a.sqrt().map(|v| v == 2.0).pr(0.99999));
+        assert!(a.ln().exp().map(|v| (v - 4.0).abs() < 1e-12).pr(0.99999));
+        assert!(a.sin().abs().map(|v| v == 4.0_f64.sin().abs()).pr(0.99999));
+        assert!(a.cos().map(|v| v == 4.0_f64.cos()).pr(0.99999));
+    }
 }
@@ -0,0 +1,232 @@
+use crate::inspect::{self, Inspect};
+use crate::law::Law;
+use crate::symbolic::{Expr, ToExpr};
+use crate::{context, design, importance, Graph, Rng, Uncertain};
+use num_traits::{Float, NumCast, ToPrimitive};
+use rand_pcg::Pcg32;
+use std::error::Error;
+use std::fmt;
+
+const PILOT_SAMPLES: usize = 1000;
+const MIN_ACCEPTANCE: f64 = 0.01;
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// Error returned by [`truncate`](Uncertain::truncate) if the uncertain
+/// value can not be truncated to the requested range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TruncationError {
+    /// The lower bound of the range is larger than the upper bound.
+    InvalidRange,
+    /// Too few samples lie within the range for rejection sampling
+    /// to be effective. Contains the estimated fraction of samples
+    /// which would be accepted.
+    LowAcceptance(f64),
+}
+
+impl fmt::Display for TruncationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TruncationError::InvalidRange => write!(f, "Truncation range is invalid"),
+            TruncationError::LowAcceptance(acceptance) => write!(
+                f,
+                "Only {} of samples lie within the truncation range",
+                acceptance
+            ),
+        }
+    }
+}
+
+impl Error for TruncationError {}
+
+pub struct Truncated<U>
+where
+    U: Uncertain,
+    U::Value: Float,
+{
+    uncertain: U,
+    low: U::Value,
+    high: U::Value,
+    law: Option<Law>,
+}
+
+impl<U> Truncated<U>
+where
+    U: Uncertain,
+    U::Value: Float,
+{
+    pub fn new(uncertain: U, low: U::Value, high: U::Value) -> Result<Self, TruncationError> {
+        if low.is_nan() || high.is_nan() || low > high {
+            return Err(TruncationError::InvalidRange);
+        }
+
+        let law = inspect::law(&uncertain);
+        let acceptance = match (law, low.to_f64(), high.to_f64()) {
+            (Some(law), Some(low), Some(high)) => law.mass(low, high),
+            _ => {
+                let _frame = context::isolate();
+                let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+                let accepted = (0..PILOT_SAMPLES)
+                    .map(|epoch| uncertain.sample(&mut rng, epoch))
+                    .filter(|v| low <= *v && *v <= high)
+                    .count();
+                accepted as f64 / PILOT_SAMPLES as f64
+            }
+        };
+        let fast_path = law.is_some() && acceptance > 0.0;
+        if acceptance < MIN_ACCEPTANCE && !fast_path {
+            return Err(TruncationError::LowAcceptance(acceptance));
+        }
+
+        let law = law.and_then(|law| Some(law.truncate(low.to_f64()?, high.to_f64()?)));
+        Ok(Self {
+            uncertain,
+            low,
+            high,
+            law,
+        })
+    }
+}
+
+impl<U> Uncertain for Truncated<U>
+where
+    U: Uncertain,
+    U::Value: Float,
+{
+    type Value = U::Value;
+
+    fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
+        if let Some(law) = &self.law {
+            let value =
+                importance::value().unwrap_or_else(|| law.inverse_cdf(design::uniform(self, rng)));
+            return <U::Value as NumCast>::from(value).unwrap_or_else(U::Value::nan);
+        }
+
+        let mut rejected = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let value = self.uncertain.sample(rng, epoch);
+            if self.low <= value && value <= self.high {
+                return value;
+            }
+            // Values which are shared by reference return the same sample
+            // for the whole epoch, so retrying can not succeed.
+            let bits = value.integer_decode();
+            if rejected == Some(bits) {
+                break;
+            }
+            rejected = Some(bits);
+        }
+        U::Value::nan()
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for Truncated<U>
+where
+    U: Uncertain,
+    U::Value: Float,
+{
+    fn law(&self) -> Option<Law> {
+        self.law
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let input = inspect::visit(&self.uncertain, graph);
+        let (low, high) = (
+            self.low.to_f64().unwrap_or(f64::NAN),
+            self.high.to_f64().unwrap_or(f64::NAN),
+        );
+        graph.add(format!("truncate({}, {})", low, high), vec![input])
+    }
+}
+
+impl<U> ToExpr for Truncated<U>
+where
+    U: Uncertain,
+    U::Value: Float,
+{
+    fn expr(&self) -> Expr {
+        Expr::source(self, self.law, "truncate")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Distribution, Measurement, TruncationError, Uncertain};
+    use rand_distr::{Exp, Normal};
+    use rand_pcg::Pcg32;
+    use std::cell::Cell;
+
+    #[test]
+    fn truncated_values_lie_in_range() {
+        let x = Distribution::from(Normal::new(0.0, 1.0).unwrap())
+            .truncate(0.0, 1.0)
+            .unwrap();
+        assert!(x.map(|x| (0.0..=1.0).contains(&x)).pr(0.9999));
+
+        let y = Measurement::normal(0.0, 1.0)
+            .unwrap()
+            .truncate(3.0, 10.0)
+            .unwrap();
+        assert!(y.map(|y| (3.0..=10.0).contains(&y)).pr(0.9999));
+    }
+
+    #[test]
+    fn truncation_preserves_shape() {
+        // a symmetric truncation does not move the mean, but a one sided one does
+        let x = Measurement::normal(2.0, 1.0)
+            .unwrap()
+            .truncate(1.0, 3.0)
+            .unwrap();
+        assert!((x.expect(0.05).unwrap() - 2.0).abs() < 0.05);
+
+        // E(X | X > 0) = sqrt(2 / pi) for X ~ N(0, 1)
+        let x = Distribution::from(Normal::new(0.0, 1.0).unwrap())
+            .truncate(0.0, f64::INFINITY)
+            .unwrap();
+        let want = (2.0 / std::f64::consts::PI).sqrt();
+        assert!((x.expect(0.05).unwrap() - want).abs() < 0.05);
+    }
+
+    #[test]
+    fn bad_truncations_are_reported() {
+        let x = Measurement::uniform(0.0, 1.0).unwrap();
+        assert_eq!(
+            x.truncate(2.0, 1.0).err(),
+            Some(TruncationError::InvalidRange)
+        );
+
+        let x = Measurement::uniform(0.0, 1.0).unwrap();
+        assert_eq!(
+            x.truncate(2.0, 3.0).err(),
+            Some(TruncationError::LowAcceptance(0.0))
+        );
+
+        let x = Distribution::from(Exp::new(1.0).unwrap());
+        assert!(matches!(
+            x.truncate(10.0, 11.0),
+            Err(TruncationError::LowAcceptance(_))
+        ));
+    }
+
+    #[test]
+    fn shared_values_outside_of_range_are_not_retried() {
+        let calls = Cell::new(0);
+        let x = Distribution::from(Normal::new(0.0, 1.0).unwrap())
+            .map(|x| {
+                calls.set(calls.get() + 1);
+                x
+            })
+            .into_ref();
+        let y = (&x).truncate(0.0, f64::INFINITY).unwrap();
+        calls.set(0);
+
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let samples: Vec<f64> = (0..100).map(|epoch| y.sample(&mut rng, epoch)).collect();
+        assert!(samples.iter().any(|y| y.is_nan()));
+        assert!(samples.iter().all(|y| y.is_nan() || *y >= 0.0));
+        assert!(calls.get() <= 100);
+    }
+}
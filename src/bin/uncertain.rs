@@ -0,0 +1,421 @@
+//! Evaluate the queries stated in a model description, see [`uncertain::Model`].
+
+use std::fmt::Write as _;
+use std::io::{self, Read};
+use std::time::Duration;
+use std::{env, fs, process};
+use uncertain::{Error, Model, Options, Query, Report, Sampling, TestReport, Uncertain};
+
+const USAGE: &str = "\
+Evaluate the queries in a model file.
+
+Usage: uncertain [OPTIONS] <MODEL>
+
+Arguments:
+  <MODEL>  Path of the model file, or `-` to read from standard input
+
+Options:
+  --seed <SEED>             Seed for the random number generator
+  --max-samples <SAMPLES>   Maximum number of samples per query
+  --max-duration <SECONDS>  Maximum duration of each query
+  --sampling <SAMPLING>     How samples are chosen, `random` (default),
+                            `halton`, `antithetic` or `lhs:<POINTS>`
+  --format <FORMAT>         Output format, `table` (default) or `json`
+  -h, --help                Print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Json,
+}
+
+struct Args {
+    path: String,
+    options: Options,
+    format: Format,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Args>, String> {
+    let mut path = None;
+    let mut options = Options::new();
+    let mut format = Format::Table;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for `{}`", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--seed" => {
+                let seed = value(&arg)?;
+                let seed = seed
+                    .parse()
+                    .map_err(|_| format!("Invalid seed `{}`", seed))?;
+                options = options.seed(seed);
+            }
+            "--max-samples" => {
+                let samples = value(&arg)?;
+                let samples = samples
+                    .parse()
+                    .map_err(|_| format!("Invalid number of samples `{}`", samples))?;
+                options = options.max_samples(samples);
+            }
+            "--max-duration" => {
+                let seconds = value(&arg)?;
+                let duration = seconds
+                    .parse()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or_else(|| format!("Invalid duration `{}`", seconds))?;
+                options = options.max_duration(duration);
+            }
+            "--sampling" => {
+                let sampling = value(&arg)?;
+                let points = sampling.strip_prefix("lhs:").map(str::parse);
+                options = options.sampling(match (sampling.as_str(), points) {
+                    ("random", _) => Sampling::Random,
+                    ("halton", _) => Sampling::Halton,
+                    ("antithetic", _) => Sampling::Antithetic,
+                    (_, Some(Ok(points))) if points > 0 => Sampling::LatinHypercube { points },
+                    _ => return Err(format!("Unknown sampling `{}`", sampling)),
+                });
+            }
+            "--format" => {
+                format = match value(&arg)?.as_str() {
+                    "table" => Format::Table,
+                    "json" => Format::Json,
+                    other => return Err(format!("Unknown format `{}`", other)),
+                };
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option `{}`", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("Unexpected argument `{}`", arg)),
+        }
+    }
+    let path = path.ok_or_else(|| "Missing model file".to_string())?;
+    Ok(Some(Args {
+        path,
+        options,
+        format,
+    }))
+}
+
+/// The outcome of a single query.
+enum Outcome {
+    Pr(TestReport),
+    Estimate(Report<f64>),
+}
+
+fn run(query: Query, options: &Options) -> Result<Outcome, Error> {
+    match query {
+        Query::Pr {
+            event, probability, ..
+        } => event.pr_with(probability, options).map(Outcome::Pr),
+        Query::Expect {
+            value, precision, ..
+        } => value.expect_with(precision, options).map(Outcome::Estimate),
+        Query::Quantile {
+            value,
+            quantile,
+            precision,
+            ..
+        } => value
+            .quantile_with(quantile, precision, options)
+            .map(Outcome::Estimate),
+    }
+}
+
+/// The state of a query which failed after taking samples.
+struct Partial {
+    estimate: f64,
+    two_sigma_error: f64,
+    samples: usize,
+    /// Not known for interrupted queries.
+    non_finite: Option<usize>,
+}
+
+fn partial(err: &Error) -> Option<Partial> {
+    match err {
+        Error::NonConvergence(err) => Some(Partial {
+            estimate: err.non_converged_value(),
+            two_sigma_error: err.two_sigma_error(),
+            samples: err.samples(),
+            non_finite: Some(err.non_finite_samples()),
+        }),
+        Error::Interrupted(partial) => Some(Partial {
+            estimate: partial.estimate(),
+            two_sigma_error: partial.two_sigma_error(),
+            samples: partial.samples(),
+            non_finite: None,
+        }),
+        _ => None,
+    }
+}
+
+/// Tests only sample booleans, which are never `NaN` or infinite.
+const PR_NON_FINITE: usize = 0;
+
+fn table(results: &[(String, Result<Outcome, Error>)]) -> String {
+    let rows: Vec<[String; 5]> = results
+        .iter()
+        .map(|(query, outcome)| match outcome {
+            Ok(Outcome::Pr(report)) => [
+                query.clone(),
+                report.result().to_string(),
+                String::new(),
+                report.samples().to_string(),
+                PR_NON_FINITE.to_string(),
+            ],
+            Ok(Outcome::Estimate(report)) => [
+                query.clone(),
+                report.value().to_string(),
+                report.two_sigma_error().to_string(),
+                report.samples().to_string(),
+                report.non_finite_samples().to_string(),
+            ],
+            Err(err) => match partial(err) {
+                Some(partial) => [
+                    query.clone(),
+                    format!("error: {}", err),
+                    if partial.estimate.is_nan() {
+                        String::new()
+                    } else {
+                        partial.two_sigma_error.to_string()
+                    },
+                    partial.samples.to_string(),
+                    partial
+                        .non_finite
+                        .map(|n| n.to_string())
+                        .unwrap_or_default(),
+                ],
+                None => [
+                    query.clone(),
+                    format!("error: {}", err),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ],
+            },
+        })
+        .collect();
+    let header = [
+        "query".to_string(),
+        "result".to_string(),
+        "± (2σ)".to_string(),
+        "samples".to_string(),
+        "non-finite".to_string(),
+    ];
+    let mut widths = [0; 5];
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths.iter()) {
+            let padding = width - cell.chars().count();
+            write!(line, "{}{}  ", cell, " ".repeat(padding)).unwrap();
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json(results: &[(String, Result<Outcome, Error>)]) -> String {
+    let objects: Vec<String> = results
+        .iter()
+        .map(|(query, outcome)| {
+            let fields = match outcome {
+                Ok(Outcome::Pr(report)) => format!(
+                    "\"result\": {}, \"samples\": {}, \"non_finite_samples\": {}",
+                    report.result(),
+                    report.samples(),
+                    PR_NON_FINITE
+                ),
+                Ok(Outcome::Estimate(report)) => format!(
+                    "\"result\": {}, \"two_sigma_error\": {}, \"samples\": {}, \"non_finite_samples\": {}",
+                    json_number(report.value()),
+                    json_number(report.two_sigma_error()),
+                    report.samples(),
+                    report.non_finite_samples()
+                ),
+                Err(err) => {
+                    let mut fields = format!("\"error\": {}", json_string(&err.to_string()));
+                    if let Some(partial) = partial(err) {
+                        write!(
+                            fields,
+                            ", \"estimate\": {}, \"two_sigma_error\": {}, \"samples\": {}",
+                            json_number(partial.estimate),
+                            json_number(partial.two_sigma_error),
+                            partial.samples
+                        )
+                        .unwrap();
+                        if let Some(non_finite) = partial.non_finite {
+                            write!(fields, ", \"non_finite_samples\": {}", non_finite).unwrap();
+                        }
+                    }
+                    fields
+                }
+            };
+            format!("  {{\"query\": {}, {}}}", json_string(query), fields)
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(2);
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => fail(&format!("{}\n\n{}", err, USAGE)),
+    };
+
+    let source = if args.path == "-" {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map(|_| source)
+            .map_err(|err| err.to_string())
+    } else {
+        fs::read_to_string(&args.path).map_err(|err| format!("{}: {}", args.path, err))
+    };
+    let source = source.unwrap_or_else(|err| fail(&err));
+    let model = Model::parse(&source).unwrap_or_else(|err| {
+        fail(&format!(
+            "{}:{}:{}: {}",
+            args.path,
+            err.line(),
+            err.column(),
+            err.message()
+        ))
+    });
+
+    let results: Vec<(String, Result<Outcome, Error>)> = model
+        .into_queries()
+        .into_iter()
+        .map(|query| (query.to_string(), run(query, &args.options)))
+        .collect();
+    let output = match args.format {
+        Format::Table => table(&results),
+        Format::Json => json(&results),
+    };
+    print!("{}", output);
+
+    if results.iter().any(|(_, outcome)| outcome.is_err()) {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn arguments_are_parsed() {
+        let parsed = args(&["--seed", "7", "model.txt", "--format", "json"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.path, "model.txt");
+        assert_eq!(parsed.format, Format::Json);
+        assert!(args(&["--help"]).unwrap().is_none());
+        assert!(args(&["--seed"]).is_err());
+        assert!(args(&["--sampling", "sobol", "model.txt"]).is_err());
+        assert!(args(&["--sampling", "lhs:0", "model.txt"]).is_err());
+        assert!(args(&["--sampling", "lhs:100", "model.txt"]).is_ok());
+        assert!(args(&["--max-duration", "-1", "model.txt"]).is_err());
+        assert!(args(&["a.txt", "b.txt"]).is_err());
+        assert!(args(&[]).is_err());
+    }
+
+    #[test]
+    fn results_are_formatted() {
+        let model = Model::parse(
+            "input x = normal(5, 1)
+             pr(x > 0, 0.9)
+             expect(x, 0.1)",
+        )
+        .unwrap();
+        let options = Options::new().seed(1);
+        let results: Vec<_> = model
+            .into_queries()
+            .into_iter()
+            .map(|query| (query.to_string(), run(query, &options)))
+            .collect();
+        let out = table(&results);
+        assert!(out.starts_with("query"));
+        assert!(out.contains("pr(x > 0, 0.9)  true"));
+
+        let out = json(&results);
+        assert!(out.contains("{\"query\": \"pr(x > 0, 0.9)\", \"result\": true, \"samples\": "));
+        assert!(out.contains("\"non_finite_samples\": 0}"));
+        assert!(out.contains("\"query\": \"expect(x, 0.1)\", \"result\": 5."));
+    }
+
+    #[test]
+    fn failed_results_keep_their_samples() {
+        let model = Model::parse(
+            "input x = normal(5, 1)
+             expect(x, 0.1)
+             expect(x, 0.00001)",
+        )
+        .unwrap();
+        let options = [
+            Options::new().seed(1).max_samples(5),
+            Options::new().seed(1),
+        ];
+        let results: Vec<_> = model
+            .into_queries()
+            .into_iter()
+            .zip(options.iter())
+            .map(|(query, options)| (query.to_string(), run(query, options)))
+            .collect();
+
+        let out = table(&results);
+        let rows: Vec<&str> = out.lines().collect();
+        assert!(rows[1].ends_with("  5"));
+        assert!(rows[2].ends_with("10000    0"));
+
+        let out = json(&results);
+        assert!(out.contains("\"estimate\": 5."));
+        assert!(out.contains("\"samples\": 5}"));
+        assert!(out.contains("\"samples\": 10000, \"non_finite_samples\": 0}"));
+    }
+}
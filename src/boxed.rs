@@ -1,5 +1,8 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::{self, Inspect};
+use crate::law::Law;
+use crate::{Density, Graph, Rng, Uncertain};
 use std::boxed::Box;
+use std::sync::Arc;
 
 /// An opaque uncertain value.
 ///
@@ -7,6 +10,7 @@ use std::boxed::Box;
 /// See [`into_boxed`](Uncertain::into_boxed).
 pub struct BoxedUncertain<T> {
     ptr: Box<dyn Uncertain<Value = T> + Send>,
+    name: &'static str,
 }
 
 impl<T> BoxedUncertain<T> {
@@ -16,6 +20,7 @@ impl<T> BoxedUncertain<T> {
     {
         Self {
             ptr: Box::new(contained),
+            name: std::any::type_name::<U>(),
         }
     }
 }
@@ -26,6 +31,27 @@ impl<T> Uncertain for BoxedUncertain<T> {
     fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
         self.ptr.sample(rng, epoch)
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<T> Inspect for BoxedUncertain<T> {
+    fn law(&self) -> Option<Law> {
+        self.ptr.inspect()?.law()
+    }
+
+    fn density(&self) -> Option<Arc<dyn Density + Send + Sync>> {
+        self.ptr.inspect()?.density()
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        match self.ptr.inspect() {
+            Some(inspect) => inspect.visit(graph),
+            None => inspect::leaf(self.name, graph),
+        }
+    }
 }
 
 #[cfg(test)]
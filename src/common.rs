@@ -0,0 +1,47 @@
+//! Common random numbers for named inputs.
+
+use crate::context::{self, Context, Guard};
+use crate::{sensitivity, Rng};
+use rand::{Rng as _, SeedableRng};
+
+fn slot(ctx: &mut Context) -> &mut Option<u64> {
+    &mut ctx.salt
+}
+
+/// Start sharing named inputs, until the returned guard is dropped. Inputs
+/// with the same name draw the same random numbers, which are derived from
+/// a salt drawn from `rng`. If inputs are already shared, the existing salt
+/// is kept and nothing is drawn.
+pub(crate) fn share(rng: &mut Rng) -> Guard<u64> {
+    let salt = context::with(|ctx| ctx.salt).unwrap_or_else(|| rng.gen());
+    context::enter(slot, salt)
+}
+
+/// If named inputs are being shared, returns the random number
+/// generator which the named input should use to produce its sample.
+pub(crate) fn input(name: &str) -> Option<Rng> {
+    let salt = context::with(|ctx| ctx.salt)?;
+    Some(Rng::seed_from_u64(sensitivity::hash(name) ^ salt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg32;
+
+    #[test]
+    fn inputs_with_same_name_share_numbers() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        assert!(input("x").is_none());
+        {
+            let _guard = share(&mut rng);
+            let drawn = rng.clone();
+            let _inner = share(&mut rng);
+            assert_eq!(rng, drawn);
+            let x: u64 = input("x").unwrap().gen();
+            assert_eq!(x, input("x").unwrap().gen::<u64>());
+            assert_ne!(x, input("y").unwrap().gen::<u64>());
+        }
+        assert!(input("x").is_none());
+    }
+}
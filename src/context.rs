@@ -0,0 +1,96 @@
+//! The state which a query shares with the values it samples.
+//!
+//! Every query runs in its own frame, such that queries which are computed
+//! while sampling another query, for example inside of
+//! [`flat_map`](crate::Uncertain::flat_map), neither see nor change the state
+//! of the outer query. Within a frame, every part of the state is replaced
+//! until the returned guard is dropped, which restores the previous value.
+
+use crate::{design, importance, sensitivity};
+use std::cell::RefCell;
+
+/// The state of the innermost query.
+#[derive(Default)]
+pub(crate) struct Context {
+    /// The sampling design, see [`design::uniform`].
+    pub(crate) design: Option<design::Design>,
+    /// The inputs of a sensitivity analysis, see [`sensitivity::input`].
+    pub(crate) sensitivity: Option<sensitivity::Inputs>,
+    /// The likelihood ratio of the current sample, see [`importance::input`].
+    pub(crate) importance: Option<importance::Weights>,
+    /// The salt from which named inputs derive common random
+    /// numbers, see [`common::input`](crate::common::input).
+    pub(crate) salt: Option<u64>,
+}
+
+thread_local! {
+    static STACK: RefCell<Vec<Context>> = RefCell::new(vec![Context::default()]);
+}
+
+/// Run `f` on the state of the innermost query.
+///
+/// # Panics
+///
+/// Panics if `f` accesses the state again.
+pub(crate) fn with<R>(f: impl FnOnce(&mut Context) -> R) -> R {
+    STACK.with(|stack| f(stack.borrow_mut().last_mut().unwrap()))
+}
+
+/// Removes the frame of a query, even if sampling panics.
+pub(crate) struct Frame(());
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        STACK.with(|stack| stack.borrow_mut().pop());
+    }
+}
+
+/// Start a query with an empty state, until the returned frame is dropped.
+pub(crate) fn isolate() -> Frame {
+    STACK.with(|stack| stack.borrow_mut().push(Context::default()));
+    Frame(())
+}
+
+/// Restores part of the state, even if sampling panics.
+pub(crate) struct Guard<T: 'static> {
+    slot: fn(&mut Context) -> &mut Option<T>,
+    previous: Option<T>,
+}
+
+impl<T> Drop for Guard<T> {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        with(|ctx| *(self.slot)(ctx) = previous);
+    }
+}
+
+/// Replace the part of the state given by `slot` with `value`, until the
+/// returned guard is dropped.
+pub(crate) fn enter<T>(slot: fn(&mut Context) -> &mut Option<T>, value: T) -> Guard<T> {
+    let previous = with(|ctx| slot(ctx).replace(value));
+    Guard { slot, previous }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_restore_previous_state() {
+        fn salt(ctx: &mut Context) -> &mut Option<u64> {
+            &mut ctx.salt
+        }
+        assert_eq!(with(|ctx| ctx.salt), None);
+        {
+            let _outer = enter(salt, 1);
+            {
+                let _inner = enter(salt, 2);
+                assert_eq!(with(|ctx| ctx.salt), Some(2));
+                let _frame = isolate();
+                assert_eq!(with(|ctx| ctx.salt), None);
+            }
+            assert_eq!(with(|ctx| ctx.salt), Some(1));
+        }
+        assert_eq!(with(|ctx| ctx.salt), None);
+    }
+}
@@ -0,0 +1,361 @@
+//! Choosing the best of several actions with uncertain outcomes.
+
+use crate::error::Interrupted;
+use crate::expectation::check_finite;
+use crate::{common, context, math, ConvergenceError, Error, Options, Uncertain};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const STEP: usize = 10;
+const MAXS: usize = 1000;
+
+/// The fewest samples of every action before any of them are compared,
+/// such that the variances of their differences are meaningful.
+const MIN_SAMPLES: usize = 20;
+
+/// Choose the action with the highest expected utility, where the outcome
+/// of every action is uncertain.
+///
+/// The actions are raced against each other: all actions which are still in
+/// the race are sampled in the same epochs, and after every batch of samples,
+/// the actions which are worse than the current leader with the desired
+/// confidence are eliminated. Like the [sequential probability ratio
+/// test][sprt] used by [`pr`](Uncertain::pr), the race stops as soon as the
+/// evidence suffices, so clearly inferior actions are only sampled a few times.
+///
+/// Inputs which are [`named`](Uncertain::named) the same draw the same random
+/// numbers for all actions, see [`compare`](crate::compare). This makes the
+/// differences between similar actions much less noisy.
+///
+/// [sprt]: https://en.wikipedia.org/wiki/Sequential_probability_ratio_test
+///
+/// # Examples
+///
+/// Basic usage: choose the number of servers which balances the cost of
+/// the servers against the cost of a slow response.
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, Decision, Options};
+///
+/// let latency = |servers: f64| {
+///     let load = Measurement::normal(100.0, 20.0).unwrap().named("load");
+///     load.map(move |load| load / servers)
+/// };
+///
+/// let mut decision = Decision::new(|(servers, latency): (f64, f64)| -servers - latency);
+/// for servers in 1..=20 {
+///     let servers = servers as f64;
+///     decision = decision.action(latency(servers).map(move |latency| (servers, latency)));
+/// }
+/// let choice = decision.best(0.95, 0.05, &Options::new()).unwrap();
+/// assert_eq!(choice.best(), 9); // ten servers
+/// ```
+pub struct Decision<U, M> {
+    actions: Vec<U>,
+    utility: M,
+}
+
+/// The result of a [`Decision`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Choice {
+    best: usize,
+    utilities: Vec<f64>,
+    samples: Vec<usize>,
+    non_finite: usize,
+}
+
+impl Choice {
+    /// The index of the best action, in the order in which
+    /// the actions were added.
+    pub fn best(&self) -> usize {
+        self.best
+    }
+
+    /// The estimated expected utility of `action`. Since actions are
+    /// eliminated as soon as they are known to be worse, the estimates of
+    /// inferior actions are less precise than that of the best action.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `action` is out of bounds.
+    pub fn expected_utility(&self, action: usize) -> f64 {
+        self.utilities[action]
+    }
+
+    /// The number of samples of `action` which were used to
+    /// estimate its expected utility.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `action` is out of bounds.
+    pub fn samples(&self, action: usize) -> usize {
+        self.samples[action]
+    }
+
+    /// The number of samples which were `NaN` or infinite.
+    pub fn non_finite_samples(&self) -> usize {
+        self.non_finite
+    }
+}
+
+/// Running sums of the utilities of the actions, and of their products.
+struct Sums {
+    values: Vec<f64>,
+    products: Vec<Vec<f64>>,
+    count: usize,
+}
+
+impl Sums {
+    fn mean(&self, action: usize) -> f64 {
+        self.values[action] / self.count as f64
+    }
+
+    /// The mean of the paired differences `a - b`, and its standard error.
+    fn difference(&self, a: usize, b: usize) -> (f64, f64) {
+        let n = self.count as f64;
+        let mean = self.mean(a) - self.mean(b);
+        let squares = self.products[a][a] - 2.0 * self.products[a][b] + self.products[b][b];
+        let variance = ((squares - n * mean * mean) / (n - 1.0)).max(0.0);
+        (mean, (variance / n).sqrt())
+    }
+}
+
+impl<U, M> Decision<U, M>
+where
+    U: Uncertain,
+    M: Fn(U::Value) -> f64,
+{
+    /// Create a decision without any actions, which
+    /// compares outcomes by their `utility`.
+    pub fn new(utility: M) -> Self {
+        Self {
+            actions: Vec::new(),
+            utility,
+        }
+    }
+
+    /// Add an action with an uncertain `outcome`. To add actions whose
+    /// outcomes have different types, use [`into_boxed`](Uncertain::into_boxed).
+    pub fn action(mut self, outcome: U) -> Self {
+        self.actions.push(outcome);
+        self
+    }
+
+    /// Find the action with the highest expected utility, such that with
+    /// probability `confidence`, no other action is better by more than
+    /// `precision`.
+    ///
+    /// The differences between actions are assumed to be approximately normal,
+    /// and the confidence accounts for all comparisons made during the race.
+    /// Since the actions are sampled in the same epochs, they are always
+    /// sampled randomly, regardless of [`Options::sampling`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `confidence` is not in `(0, 1)`, if `precision <= 0`,
+    /// or if no action was found to be the best. In the last case, the
+    /// [`ConvergenceError`] contains the expected utility of the leading action
+    /// and the standard error of its difference to the closest competitor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no actions.
+    pub fn best(
+        &self,
+        confidence: f32,
+        precision: f64,
+        options: &Options,
+    ) -> Result<Choice, Error> {
+        if confidence.is_nan() || confidence <= 0.0 || confidence >= 1.0 {
+            return Err(Error::InvalidProbability(confidence));
+        }
+        if precision.is_nan() || precision <= 0.0 {
+            return Err(Error::InvalidPrecision(precision));
+        }
+        assert!(!self.actions.is_empty(), "a decision needs actions");
+
+        let actions = self.actions.len();
+        let _frame = context::isolate();
+        let mut rng = options.rng();
+        let budget = options.budget();
+        let mut sums = Sums {
+            values: vec![0.0; actions],
+            products: vec![vec![0.0; actions]; actions],
+            count: 0,
+        };
+        let mut samples = vec![0; actions];
+        let mut racing: Vec<usize> = (0..actions).collect();
+        let mut utilities = vec![0.0; actions];
+        let mut non_finite = 0;
+        let mut leader = 0;
+        let mut gap = f64::INFINITY;
+
+        for batch in 0..MAXS {
+            for batch_step in 0..STEP {
+                let epoch = STEP * batch + batch_step;
+                if let Err(limit) = budget.check(epoch) {
+                    return Err(Error::Interrupted(Interrupted::new(
+                        limit,
+                        epoch,
+                        (sums.count > 0).then(|| (sums.mean(leader), gap)),
+                    )));
+                }
+                let _guard = common::share(&mut rng);
+                let mut finite = true;
+                for &action in &racing {
+                    let utility = (self.utility)(self.actions[action].sample(&mut rng, epoch));
+                    finite &= check_finite(utility, epoch, options, &mut non_finite)?;
+                    utilities[action] = utility;
+                }
+                if !finite {
+                    continue;
+                }
+                for &a in &racing {
+                    sums.values[a] += utilities[a];
+                    for &b in &racing {
+                        sums.products[a][b] += utilities[a] * utilities[b];
+                    }
+                }
+                sums.count += 1;
+            }
+
+            if racing
+                .iter()
+                .any(|&action| !sums.values[action].is_finite())
+            {
+                return Err(Error::NonConvergence(ConvergenceError {
+                    samples: sums.count,
+                    non_finite,
+                    ..ConvergenceError::nan(precision)
+                }));
+            }
+            if racing.len() > 1 && sums.count < MIN_SAMPLES {
+                continue;
+            }
+
+            leader = *racing
+                .iter()
+                .max_by(|a, b| sums.mean(**a).total_cmp(&sums.mean(**b)))
+                .unwrap();
+            // Split the allowed error among the comparisons, such that it
+            // sums to at most `1 - confidence` over all batches.
+            let error = (1.0 - confidence as f64) * 6.0
+                / (std::f64::consts::PI * (batch + 1) as f64).powi(2)
+                / (actions - 1).max(1) as f64;
+            let z = math::normal_inverse_cdf(1.0 - error);
+            let mut worst = f64::NEG_INFINITY;
+            gap = 0.0;
+            racing.retain(|&action| {
+                if action == leader {
+                    return true;
+                }
+                let (mean, std_error) = sums.difference(leader, action);
+                if mean - z * std_error > 0.0 {
+                    samples[action] = sums.count;
+                    return false;
+                }
+                if z * std_error - mean > worst {
+                    worst = z * std_error - mean;
+                    gap = std_error;
+                }
+                true
+            });
+
+            if worst <= precision {
+                for &action in &racing {
+                    samples[action] = sums.count;
+                }
+                let utilities = (0..actions)
+                    .map(|action| sums.values[action] / samples[action] as f64)
+                    .collect();
+                return Ok(Choice {
+                    best: leader,
+                    utilities,
+                    samples,
+                    non_finite,
+                });
+            }
+        }
+
+        Err(Error::NonConvergence(ConvergenceError {
+            sample_mean: sums.mean(leader),
+            std_error: gap,
+            precision,
+            samples: sums.count,
+            non_finite,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Distribution, Measurement, PointMass};
+    use rand_distr::Normal;
+
+    #[test]
+    fn inferior_actions_are_eliminated_early() {
+        let means = [1.0, 3.0, 2.5, 0.0];
+        let mut decision = Decision::new(|value: f64| value);
+        for mean in means {
+            decision = decision.action(Distribution::from(Normal::new(mean, 1.0).unwrap()));
+        }
+        let choice = decision.best(0.99, 0.01, &Options::new()).unwrap();
+        assert_eq!(choice.best(), 1);
+        assert!((choice.expected_utility(1) - 3.0).abs() < 0.5);
+        assert!(choice.samples(3) < choice.samples(2));
+        assert!(choice.samples(2) <= choice.samples(1));
+    }
+
+    #[test]
+    fn named_inputs_are_shared_by_actions() {
+        let outcome = |offset: f64| {
+            let noise = Measurement::normal(0.0, 100.0).unwrap().named("noise");
+            noise.map(move |noise| noise + offset)
+        };
+        let decision = Decision::new(|value: f64| value)
+            .action(outcome(0.0))
+            .action(outcome(1.0));
+        let choice = decision.best(0.99, 0.1, &Options::new()).unwrap();
+        assert_eq!(choice.best(), 1);
+        assert!(choice.samples(0) <= MIN_SAMPLES);
+
+        let same = Decision::new(|value: f64| value)
+            .action(PointMass::new(1.0))
+            .action(PointMass::new(1.0));
+        assert!(same.best(0.99, 0.1, &Options::new()).is_ok());
+        assert!(matches!(
+            same.best(1.0, 0.1, &Options::new()),
+            Err(Error::InvalidProbability(_))
+        ));
+    }
+
+    #[test]
+    fn queries_can_be_nested_in_actions() {
+        let model = || {
+            let a = Measurement::normal(0.0, 1.0).unwrap().named("a");
+            let b = Measurement::normal(5.0, 2.0).unwrap().named("b");
+            a.add(b)
+        };
+        let outcome = move |offset: f64| {
+            let noise = Measurement::normal(0.0, 100.0).unwrap().named("noise");
+            noise.map(move |noise| {
+                // the inner queries neither share the inputs of the decision,
+                // nor stop the decision from sharing them
+                let indices = model().sobol_indices(500).unwrap();
+                assert!((indices.total("a").unwrap() - 0.2).abs() < 0.1);
+                let inner = Measurement::normal(0.0, 1.0).unwrap().named("noise");
+                let variance = inner.map(|x| x * x).expect(0.2).unwrap();
+                assert!((variance - 1.0).abs() < 0.2);
+                noise + offset
+            })
+        };
+        let decision = Decision::new(|value: f64| value)
+            .action(outcome(0.0))
+            .action(outcome(1.0));
+        let choice = decision.best(0.99, 0.1, &Options::new()).unwrap();
+        assert_eq!(choice.best(), 1);
+        assert!(choice.samples(0) <= MIN_SAMPLES);
+    }
+}
@@ -0,0 +1,604 @@
+use crate::inspect::Inspect;
+use crate::law::{Law, Shape};
+use crate::measurement::check_finite;
+use crate::symbolic::{Expr, ToExpr};
+use crate::{design, importance, math, Graph, Measurement, ParameterError, Rng, Uncertain};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Distributions with a known density and cumulative distribution function.
+///
+/// For discrete distributions, which only take integer values, the density
+/// is the probability mass function. Distributions with a density can be
+/// sampled using their [`inverse_cdf`](Density::inverse_cdf), see [`Source`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Density, Exponential};
+///
+/// let x = Exponential::new(2.0).unwrap();
+/// assert_eq!(x.log_pdf(0.0), 2f64.ln());
+/// assert!((x.cdf(x.inverse_cdf(0.3)) - 0.3).abs() < 1e-12);
+/// ```
+pub trait Density {
+    /// The logarithm of the probability density at `x`, or of the
+    /// probability of `x` for discrete distributions. This is negative
+    /// infinity outside of the support of the distribution.
+    fn log_pdf(&self, x: f64) -> f64;
+
+    /// The probability of values less than or equal to `x`.
+    fn cdf(&self, x: f64) -> f64;
+
+    /// The smallest value `x` for which `cdf(x) >= p`, where `p` is in `[0, 1]`.
+    fn inverse_cdf(&self, p: f64) -> f64;
+}
+
+impl Density for Law {
+    fn log_pdf(&self, x: f64) -> f64 {
+        Law::log_pdf(self, x)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        Law::cdf(self, x)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        Law::inverse_cdf(self, p)
+    }
+}
+
+impl Density for Measurement {
+    fn log_pdf(&self, x: f64) -> f64 {
+        self.law().unwrap().log_pdf(x)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        self.law().unwrap().cdf(x)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        self.law().unwrap().inverse_cdf(p)
+    }
+}
+
+/// Invert the cumulative distribution function of a continuous
+/// distribution with support `[low, high]`, using Newton's method
+/// safeguarded by bisection.
+fn invert<D: Density>(dist: &D, p: f64, guess: f64, (low, high): (f64, f64)) -> f64 {
+    if p <= 0.0 {
+        return low;
+    } else if p >= 1.0 {
+        return high;
+    }
+    let (mut low, mut high) = (low, high);
+    let mut x = guess.max(low).min(high);
+    for _ in 0..200 {
+        let err = dist.cdf(x) - p;
+        if err == 0.0 {
+            break;
+        } else if err < 0.0 {
+            low = x;
+        } else {
+            high = x;
+        }
+        let mut next = x - err / dist.log_pdf(x).exp();
+        if !(next > low && next < high) {
+            next = if high.is_finite() {
+                (low + high) / 2.0
+            } else {
+                2.0 * x.max(1.0)
+            };
+        }
+        if (next - x).abs() <= 4.0 * f64::EPSILON * x.abs() {
+            return next;
+        }
+        x = next;
+    }
+    x
+}
+
+/// Invert the cumulative distribution function of a discrete distribution
+/// on the integers in `[0, max]`, starting the search at `guess`.
+fn invert_discrete<D: Density>(dist: &D, p: f64, guess: f64, max: f64) -> f64 {
+    let mut k = guess.floor().max(0.0).min(max);
+    while k < max && dist.cdf(k) < p {
+        k += 1.0;
+    }
+    while k > 0.0 && dist.cdf(k - 1.0) >= p {
+        k -= 1.0;
+    }
+    k
+}
+
+/// The logarithm of the binomial coefficient `n` choose `k`.
+fn ln_choose(n: f64, k: f64) -> f64 {
+    -math::ln_beta(k + 1.0, n - k + 1.0) - (n + 1.0).ln()
+}
+
+/// The [normal distribution](https://en.wikipedia.org/wiki/Normal_distribution)
+/// with the given mean and standard deviation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Normal {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Normal {
+    /// A normal distribution with the given mean and standard deviation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter is not finite, or if the
+    /// standard deviation is not positive.
+    pub fn new(mean: f64, std_dev: f64) -> Result<Self, ParameterError> {
+        check_finite(&[mean, std_dev])?;
+        if std_dev <= 0.0 {
+            return Err(ParameterError::NotPositive);
+        }
+        Ok(Self { mean, std_dev })
+    }
+
+    fn law(&self) -> Law {
+        Law::new(Shape::Normal {
+            mean: self.mean,
+            std_dev: self.std_dev,
+        })
+    }
+}
+
+impl Density for Normal {
+    fn log_pdf(&self, x: f64) -> f64 {
+        self.law().log_pdf(x)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        self.law().cdf(x)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        self.law().inverse_cdf(p)
+    }
+}
+
+/// The [uniform distribution](https://en.wikipedia.org/wiki/Continuous_uniform_distribution)
+/// over a range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Uniform {
+    low: f64,
+    high: f64,
+}
+
+impl Uniform {
+    /// A uniform distribution over the range `[low, high]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter is not finite, or if `low >= high`.
+    pub fn new(low: f64, high: f64) -> Result<Self, ParameterError> {
+        check_finite(&[low, high])?;
+        if low >= high {
+            return Err(ParameterError::InvalidRange);
+        }
+        Ok(Self { low, high })
+    }
+
+    fn law(&self) -> Law {
+        Law::new(Shape::Uniform {
+            low: self.low,
+            high: self.high,
+        })
+    }
+}
+
+impl Density for Uniform {
+    fn log_pdf(&self, x: f64) -> f64 {
+        self.law().log_pdf(x)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        self.law().cdf(x)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        self.law().inverse_cdf(p)
+    }
+}
+
+/// The [exponential distribution](https://en.wikipedia.org/wiki/Exponential_distribution)
+/// with the given rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Exponential {
+    rate: f64,
+}
+
+impl Exponential {
+    /// An exponential distribution with the given rate, whose mean is `1 / rate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rate is not finite or not positive.
+    pub fn new(rate: f64) -> Result<Self, ParameterError> {
+        check_finite(&[rate])?;
+        if rate <= 0.0 {
+            return Err(ParameterError::NotPositive);
+        }
+        Ok(Self { rate })
+    }
+}
+
+impl Density for Exponential {
+    fn log_pdf(&self, x: f64) -> f64 {
+        if x < 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        self.rate.ln() - self.rate * x
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= 0.0 {
+            return 0.0;
+        }
+        -(-self.rate * x).exp_m1()
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        -(-p).ln_1p() / self.rate
+    }
+}
+
+/// The [gamma distribution](https://en.wikipedia.org/wiki/Gamma_distribution)
+/// with the given shape and scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Gamma {
+    shape: f64,
+    scale: f64,
+}
+
+impl Gamma {
+    /// A gamma distribution with the given shape and scale, whose
+    /// mean is `shape * scale`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter is not finite or not positive.
+    pub fn new(shape: f64, scale: f64) -> Result<Self, ParameterError> {
+        check_finite(&[shape, scale])?;
+        if shape <= 0.0 || scale <= 0.0 {
+            return Err(ParameterError::NotPositive);
+        }
+        Ok(Self { shape, scale })
+    }
+}
+
+impl Density for Gamma {
+    fn log_pdf(&self, x: f64) -> f64 {
+        if x < 0.0 || (x == 0.0 && self.shape > 1.0) {
+            return f64::NEG_INFINITY;
+        }
+        if x == 0.0 && self.shape == 1.0 {
+            // avoid `0 * ln(0)`, the density is that of an exponential distribution
+            return -self.scale.ln();
+        }
+        (self.shape - 1.0) * x.ln()
+            - x / self.scale
+            - libm::lgamma(self.shape)
+            - self.shape * self.scale.ln()
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        math::gamma_p(self.shape, x / self.scale)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        // The approximation by Wilson and Hilferty (1931)
+        let k = self.shape;
+        let z = math::normal_inverse_cdf(p.clamp(1e-300, 1.0 - 1e-16));
+        let cube = 1.0 - 1.0 / (9.0 * k) + z / (3.0 * k.sqrt());
+        let guess = (k * self.scale * cube.powi(3)).max(f64::MIN_POSITIVE);
+        invert(self, p, guess, (0.0, f64::INFINITY))
+    }
+}
+
+/// The [beta distribution](https://en.wikipedia.org/wiki/Beta_distribution)
+/// on `[0, 1]` with the given shape parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Beta {
+    alpha: f64,
+    beta: f64,
+}
+
+impl Beta {
+    /// A beta distribution with the shape parameters `alpha` and `beta`,
+    /// whose mean is `alpha / (alpha + beta)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter is not finite or not positive.
+    pub fn new(alpha: f64, beta: f64) -> Result<Self, ParameterError> {
+        check_finite(&[alpha, beta])?;
+        if alpha <= 0.0 || beta <= 0.0 {
+            return Err(ParameterError::NotPositive);
+        }
+        Ok(Self { alpha, beta })
+    }
+}
+
+impl Density for Beta {
+    fn log_pdf(&self, x: f64) -> f64 {
+        if !(0.0..=1.0).contains(&x) {
+            return f64::NEG_INFINITY;
+        }
+        (self.alpha - 1.0) * x.ln() + (self.beta - 1.0) * (-x).ln_1p()
+            - math::ln_beta(self.alpha, self.beta)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        math::beta_i(self.alpha, self.beta, x)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        let mean = self.alpha / (self.alpha + self.beta);
+        invert(self, p, mean, (0.0, 1.0))
+    }
+}
+
+/// The [binomial distribution](https://en.wikipedia.org/wiki/Binomial_distribution),
+/// which counts the successes in a number of independent trials.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Binomial {
+    trials: u64,
+    probability: f64,
+}
+
+impl Binomial {
+    /// A binomial distribution counting the successes in `trials` independent
+    /// trials, which each succeed with the given probability.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the probability is not in `[0, 1]`.
+    pub fn new(trials: u64, probability: f64) -> Result<Self, ParameterError> {
+        check_finite(&[probability])?;
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(ParameterError::InvalidRange);
+        }
+        Ok(Self {
+            trials,
+            probability,
+        })
+    }
+}
+
+impl Density for Binomial {
+    fn log_pdf(&self, x: f64) -> f64 {
+        let (n, p) = (self.trials as f64, self.probability);
+        if x < 0.0 || x > n || x.fract() != 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        let successes = if x > 0.0 { x * p.ln() } else { 0.0 };
+        let failures = if x < n { (n - x) * (-p).ln_1p() } else { 0.0 };
+        ln_choose(n, x) + successes + failures
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        let (n, k) = (self.trials as f64, x.floor());
+        if k < 0.0 {
+            0.0
+        } else if k >= n {
+            1.0
+        } else {
+            math::beta_i(n - k, k + 1.0, 1.0 - self.probability)
+        }
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        let (n, q) = (self.trials as f64, self.probability);
+        let z = math::normal_inverse_cdf(p);
+        let guess = n * q + z * (n * q * (1.0 - q)).sqrt();
+        invert_discrete(self, p, if guess.is_finite() { guess } else { 0.0 }, n)
+    }
+}
+
+/// The [Poisson distribution](https://en.wikipedia.org/wiki/Poisson_distribution),
+/// which counts the events occurring independently at a constant rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Poisson {
+    rate: f64,
+}
+
+impl Poisson {
+    /// A Poisson distribution with the given expected number of events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rate is not finite or not positive.
+    pub fn new(rate: f64) -> Result<Self, ParameterError> {
+        check_finite(&[rate])?;
+        if rate <= 0.0 {
+            return Err(ParameterError::NotPositive);
+        }
+        Ok(Self { rate })
+    }
+}
+
+impl Density for Poisson {
+    fn log_pdf(&self, x: f64) -> f64 {
+        if x < 0.0 || x.fract() != 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        x * self.rate.ln() - self.rate - libm::lgamma(x + 1.0)
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        if x < 0.0 {
+            return 0.0;
+        }
+        1.0 - math::gamma_p(x.floor() + 1.0, self.rate)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        let z = math::normal_inverse_cdf(p);
+        let guess = self.rate + z * self.rate.sqrt();
+        invert_discrete(
+            self,
+            p,
+            if guess.is_finite() { guess } else { 0.0 },
+            f64::INFINITY,
+        )
+    }
+}
+
+/// Wraps a distribution with a known [`Density`] and implements [`Uncertain`].
+///
+/// Values are sampled using the inverse of the cumulative distribution
+/// function, such that they work with all [sampling designs](crate::Sampling).
+/// Named sources can be drawn from a proposal distribution when using
+/// [`Importance`](crate::Importance) sampling.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Source, Gamma};
+///
+/// let x = Source::from(Gamma::new(2.0, 3.0).unwrap());
+/// assert!((x.expect(0.2).unwrap() - 6.0).abs() < 0.2);
+/// ```
+pub struct Source<D> {
+    dist: Arc<D>,
+}
+
+impl<D> From<D> for Source<D>
+where
+    D: Density,
+{
+    fn from(dist: D) -> Self {
+        Self {
+            dist: Arc::new(dist),
+        }
+    }
+}
+
+impl<D> Source<D> {
+    /// The wrapped distribution.
+    pub fn distribution(&self) -> &D {
+        &self.dist
+    }
+}
+
+impl<D> Uncertain for Source<D>
+where
+    D: Density + Send + Sync + 'static,
+{
+    type Value = f64;
+
+    fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
+        importance::value().unwrap_or_else(|| self.dist.inverse_cdf(design::uniform(self, rng)))
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<D> Inspect for Source<D>
+where
+    D: Density + Send + Sync + 'static,
+{
+    fn density(&self) -> Option<Arc<dyn Density + Send + Sync>> {
+        Some(self.dist.clone())
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        graph.add("source", Vec::new())
+    }
+}
+
+impl<D> ToExpr for Source<D>
+where
+    D: Density + Send + Sync + 'static,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "source")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_cdf_inverts_cdf() {
+        let dists: Vec<Box<dyn Density>> = vec![
+            Box::new(Normal::new(1.0, 2.0).unwrap()),
+            Box::new(Uniform::new(-1.0, 3.0).unwrap()),
+            Box::new(Exponential::new(0.5).unwrap()),
+            Box::new(Gamma::new(0.5, 2.0).unwrap()),
+            Box::new(Gamma::new(20.0, 0.1).unwrap()),
+            Box::new(Beta::new(0.5, 3.0).unwrap()),
+            Box::new(Beta::new(5.0, 2.0).unwrap()),
+        ];
+        for dist in dists.iter() {
+            for p in [1e-8, 0.01, 0.3, 0.5, 0.9, 0.999_999].iter() {
+                let x = dist.inverse_cdf(*p);
+                assert!((dist.cdf(x) - p).abs() < 1e-10 * p.max(1e-3), "{} {}", p, x);
+            }
+        }
+    }
+
+    #[test]
+    fn discrete_distributions_sum_to_one() {
+        let dists: Vec<Box<dyn Density>> = vec![
+            Box::new(Binomial::new(20, 0.3).unwrap()),
+            Box::new(Binomial::new(5, 1.0).unwrap()),
+            Box::new(Poisson::new(4.5).unwrap()),
+            Box::new(Poisson::new(1000.0).unwrap()),
+        ];
+        for dist in dists.iter() {
+            let mut cdf = 0.0;
+            for k in 0..2000 {
+                let k = k as f64;
+                cdf += dist.log_pdf(k).exp();
+                assert!((dist.cdf(k) - cdf).abs() < 1e-10);
+                let p = dist.cdf(k) - 1e-12;
+                if p > 1e-12 && dist.log_pdf(k).exp() > 1e-11 {
+                    assert_eq!(dist.inverse_cdf(p), k);
+                }
+            }
+            assert!((cdf - 1.0).abs() < 1e-10);
+            assert_eq!(dist.log_pdf(0.5), f64::NEG_INFINITY);
+        }
+    }
+
+    #[test]
+    fn sources_have_correct_moments() {
+        let x = Source::from(Beta::new(2.0, 6.0).unwrap());
+        assert!((x.expect(0.01).unwrap() - 0.25).abs() < 0.01);
+        let n = Source::from(Poisson::new(3.0).unwrap());
+        assert!((n.expect(0.1).unwrap() - 3.0).abs() < 0.1);
+        assert_eq!(x.describe().nodes()[0].label(), "source");
+    }
+
+    #[test]
+    fn gamma_density_is_finite_at_zero() {
+        let exponential = Gamma::new(1.0, 2.0).unwrap();
+        assert_eq!(exponential.log_pdf(0.0), -2f64.ln());
+        assert_eq!(Gamma::new(0.5, 2.0).unwrap().log_pdf(0.0), f64::INFINITY);
+        assert_eq!(
+            Gamma::new(2.0, 2.0).unwrap().log_pdf(0.0),
+            f64::NEG_INFINITY
+        );
+    }
+}
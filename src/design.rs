@@ -0,0 +1,380 @@
+//! Sampling designs, which replace the independent uniform random
+//! numbers used by sources with more evenly spread points.
+
+use crate::context::{self, Context, Guard};
+use crate::{math, Rng};
+use rand::distributions::{Distribution, Open01};
+use rand::seq::SliceRandom;
+
+/// The first primes, used as the bases of the Halton sequence. Sources
+/// beyond the number of primes fall back to independent random numbers,
+/// which queries report as an invalid design.
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Assigns every source a dimension of a randomly shifted Halton
+/// sequence, see [`uniform`].
+pub(crate) struct Halton {
+    sources: Vec<usize>,
+    shifts: Vec<Vec<f64>>,
+    replicates: usize,
+    replicate: usize,
+    index: u64,
+    rng: Rng,
+}
+
+/// Remembers the number drawn by every source, such that
+/// it can be reflected in the next sample.
+pub(crate) struct Antithetic {
+    uniforms: Vec<(usize, f64)>,
+    reflect: bool,
+}
+
+/// Assigns every source an independent random permutation of the
+/// strata of a Latin hypercube design, see [`uniform`].
+pub(crate) struct LatinHypercube {
+    sources: Vec<usize>,
+    strata: Vec<Vec<Vec<usize>>>,
+    replicates: usize,
+    points: usize,
+    replicate: usize,
+    index: usize,
+    rng: Rng,
+}
+
+/// Remembers the standard normal numbers behind the uniforms drawn by
+/// the sources, such that they can be perturbed to explore the
+/// neighbourhood of a previous sample, see [`propose`].
+pub(crate) struct Conditional {
+    current: Vec<(usize, f64)>,
+    drawn: Vec<(usize, f64)>,
+    correlation: f64,
+}
+
+/// The design which replaces the random numbers drawn by the sources.
+pub(crate) enum Design {
+    Halton(Halton),
+    Antithetic(Antithetic),
+    LatinHypercube(LatinHypercube),
+    Conditional(Conditional),
+}
+
+fn slot(ctx: &mut Context) -> &mut Option<Design> {
+    &mut ctx.design
+}
+
+fn enter(design: Design) -> Guard<Design> {
+    context::enter(slot, design)
+}
+
+/// Start sampling `replicates` independently shifted copies of the
+/// Halton sequence, where the shifts are drawn from `rng`. The design
+/// is active until the returned guard is dropped.
+pub(crate) fn halton(replicates: usize, rng: Rng) -> Guard<Design> {
+    enter(Design::Halton(Halton {
+        sources: Vec::new(),
+        shifts: Vec::new(),
+        replicates,
+        replicate: 0,
+        index: 0,
+        rng,
+    }))
+}
+
+/// Start sampling `replicates` independent Latin hypercube designs
+/// with the given number of points, where the strata are assigned using
+/// `rng`. The design is active until the returned guard is dropped.
+pub(crate) fn latin_hypercube(replicates: usize, points: usize, rng: Rng) -> Guard<Design> {
+    enter(Design::LatinHypercube(LatinHypercube {
+        sources: Vec::new(),
+        strata: Vec::new(),
+        replicates,
+        points,
+        replicate: 0,
+        index: 0,
+        rng,
+    }))
+}
+
+/// Select the point of the Halton or Latin hypercube design which
+/// is used by subsequent samples.
+pub(crate) fn select(replicate: usize, index: usize) {
+    context::with(|ctx| match ctx.design.as_mut() {
+        Some(Design::Halton(ctx)) => {
+            ctx.replicate = replicate;
+            // Skip the first point, which lies on the boundary.
+            ctx.index = index as u64 + 1;
+        }
+        Some(Design::LatinHypercube(ctx)) => {
+            ctx.replicate = replicate;
+            ctx.index = index;
+        }
+        _ => (),
+    })
+}
+
+/// Start sampling antithetic pairs, where the second sample of every
+/// pair reflects the numbers drawn by the sources for the first sample,
+/// see [`reflect`]. The design is active until the returned guard is dropped.
+pub(crate) fn antithetic() -> Guard<Design> {
+    enter(Design::Antithetic(Antithetic {
+        uniforms: Vec::new(),
+        reflect: false,
+    }))
+}
+
+/// Select if subsequent samples draw new numbers, or reflect
+/// the numbers drawn since the last call to `reflect(false)`.
+pub(crate) fn reflect(reflect: bool) {
+    context::with(|ctx| {
+        if let Some(Design::Antithetic(ctx)) = ctx.design.as_mut() {
+            if !reflect {
+                ctx.uniforms.clear();
+            }
+            ctx.reflect = reflect;
+        }
+    })
+}
+
+/// Start recording the numbers drawn by the sources, such that subsequent
+/// samples can be drawn close to a previous one, see [`propose`]. The
+/// design is active until the returned guard is dropped.
+pub(crate) fn conditional() -> Guard<Design> {
+    enter(Design::Conditional(Conditional {
+        current: Vec::new(),
+        drawn: Vec::new(),
+        correlation: 0.0,
+    }))
+}
+
+/// Draw the next sample close to the sample which drew `point`, see
+/// [`drawn`]. Every number is drawn from its normal distribution
+/// conditioned on having the given correlation with its previous value,
+/// such that the distribution of the sources is unchanged. Sources which
+/// do not draw through [`uniform`] are sampled independently.
+pub(crate) fn propose(point: &[(usize, f64)], correlation: f64) {
+    context::with(|ctx| {
+        if let Some(Design::Conditional(ctx)) = ctx.design.as_mut() {
+            ctx.current.clear();
+            ctx.current.extend_from_slice(point);
+            ctx.drawn.clear();
+            ctx.correlation = correlation;
+        }
+    })
+}
+
+/// The numbers drawn since the last call to [`propose`].
+pub(crate) fn drawn() -> Vec<(usize, f64)> {
+    context::with(|ctx| match ctx.design.as_mut() {
+        Some(Design::Conditional(ctx)) => std::mem::take(&mut ctx.drawn),
+        _ => Vec::new(),
+    })
+}
+
+/// The [radical inverse](https://en.wikipedia.org/wiki/Halton_sequence)
+/// of `index` in the given base.
+fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let mut value = 0.0;
+    let mut scale = 1.0 / base as f64;
+    while index > 0 {
+        value += (index % base) as f64 * scale;
+        index /= base;
+        scale /= base as f64;
+    }
+    value
+}
+
+/// A uniform random number in `(0, 1)`, which sources use to produce
+/// their sample using the inverse of their cumulative distribution function.
+///
+/// If a design is active, the number is the coordinate of the current
+/// point along the dimension assigned to `source`. Otherwise, it is
+/// drawn from `rng`.
+pub(crate) fn uniform<T: ?Sized>(source: &T, rng: &mut Rng) -> f64 {
+    let address = source as *const T as *const () as usize;
+    context::with(|ctx| match ctx.design.as_mut() {
+        Some(Design::Halton(ctx)) => ctx.coordinate(address, rng),
+        Some(Design::Antithetic(ctx)) => ctx.uniform(address, rng),
+        Some(Design::LatinHypercube(ctx)) => ctx.uniform(address),
+        Some(Design::Conditional(ctx)) => ctx.uniform(address, rng),
+        None => Open01.sample(rng),
+    })
+}
+
+impl Halton {
+    fn coordinate(&mut self, address: usize, rng: &mut Rng) -> f64 {
+        let dim = match self.sources.iter().position(|other| *other == address) {
+            Some(dim) => dim,
+            None if self.sources.len() < PRIMES.len() => {
+                let shifts_rng = &mut self.rng;
+                let shifts = (0..self.replicates)
+                    .map(|_| Open01.sample(shifts_rng))
+                    .collect();
+                self.sources.push(address);
+                self.shifts.push(shifts);
+                self.sources.len() - 1
+            }
+            None => return Open01.sample(rng),
+        };
+        let shifted = radical_inverse(self.index, PRIMES[dim]) + self.shifts[dim][self.replicate];
+        shifted.fract().max(f64::MIN_POSITIVE)
+    }
+}
+
+impl LatinHypercube {
+    fn uniform(&mut self, address: usize) -> f64 {
+        let dim = match self.sources.iter().position(|other| *other == address) {
+            Some(dim) => dim,
+            None => {
+                let (points, rng) = (self.points, &mut self.rng);
+                let strata = (0..self.replicates)
+                    .map(|_| {
+                        let mut strata: Vec<usize> = (0..points).collect();
+                        strata.shuffle(rng);
+                        strata
+                    })
+                    .collect();
+                self.sources.push(address);
+                self.strata.push(strata);
+                self.sources.len() - 1
+            }
+        };
+        let stratum = self.strata[dim][self.replicate][self.index % self.points];
+        let offset: f64 = Open01.sample(&mut self.rng);
+        (stratum as f64 + offset) / self.points as f64
+    }
+}
+
+impl Antithetic {
+    fn uniform(&mut self, address: usize, rng: &mut Rng) -> f64 {
+        let drawn = self
+            .uniforms
+            .iter()
+            .find(|(other, _)| *other == address)
+            .map(|(_, uniform)| *uniform);
+        match drawn {
+            Some(uniform) if self.reflect => 1.0 - uniform,
+            _ => {
+                let uniform = Open01.sample(rng);
+                if !self.reflect {
+                    self.uniforms.push((address, uniform));
+                }
+                uniform
+            }
+        }
+    }
+}
+
+impl Conditional {
+    fn uniform(&mut self, address: usize, rng: &mut Rng) -> f64 {
+        // Sources which are sampled repeatedly are matched by their order
+        let occurrence = self
+            .drawn
+            .iter()
+            .filter(|(other, _)| *other == address)
+            .count();
+        let previous = self
+            .current
+            .iter()
+            .filter(|(other, _)| *other == address)
+            .nth(occurrence);
+        let innovation = math::normal_inverse_cdf(Open01.sample(rng));
+        let normal = match previous {
+            Some((_, normal)) => {
+                let correlation = self.correlation;
+                correlation * normal + (1.0 - correlation * correlation).sqrt() * innovation
+            }
+            None => innovation,
+        };
+        self.drawn.push((address, normal));
+        math::normal_cdf(normal).clamp(f64::MIN_POSITIVE, 1.0 - f64::EPSILON / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg32;
+
+    #[test]
+    fn radical_inverse_is_van_der_corput() {
+        let points: Vec<f64> = (1..8).map(|idx| radical_inverse(idx, 2)).collect();
+        assert_eq!(points, vec![0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875]);
+        assert!((radical_inverse(5, 3) - 7.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sources_are_assigned_dimensions() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let (a, b) = (1u8, 2u8);
+        let _guard = halton(2, rng.clone());
+
+        let mut points = Vec::new();
+        for index in 0..100 {
+            select(0, index);
+            points.push((uniform(&a, &mut rng), uniform(&b, &mut rng)));
+            assert_eq!(points[index].0, uniform(&a, &mut rng));
+        }
+        // Every quarter of the unit square receives close to a quarter of the points
+        let lower_left = points.iter().filter(|(x, y)| *x < 0.5 && *y < 0.5).count();
+        assert!((23..=27).contains(&lower_left));
+
+        select(1, 0);
+        assert_ne!(points[0].0, uniform(&a, &mut rng));
+    }
+
+    #[test]
+    fn latin_hypercube_strata_are_sampled_once() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let (a, b) = (1u8, 2u8);
+        let _guard = latin_hypercube(1, 10, rng.clone());
+
+        let mut strata = (Vec::new(), Vec::new());
+        for index in 0..10 {
+            select(0, index);
+            strata.0.push((uniform(&a, &mut rng) * 10.0) as usize);
+            strata.1.push((uniform(&b, &mut rng) * 10.0) as usize);
+        }
+        assert_ne!(strata.0, strata.1);
+        strata.0.sort_unstable();
+        strata.1.sort_unstable();
+        assert_eq!(strata.0, (0..10).collect::<Vec<_>>());
+        assert_eq!(strata.1, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn antithetic_samples_are_reflected() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let (a, b) = (1u8, 2u8);
+        let _guard = antithetic();
+
+        reflect(false);
+        let (u, v) = (uniform(&a, &mut rng), uniform(&b, &mut rng));
+        reflect(true);
+        assert_eq!(uniform(&b, &mut rng), 1.0 - v);
+        assert_eq!(uniform(&a, &mut rng), 1.0 - u);
+        reflect(false);
+        assert_ne!(uniform(&a, &mut rng), u);
+    }
+
+    #[test]
+    fn conditional_samples_are_correlated() {
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let (a, b) = (1u8, 2u8);
+        let _guard = conditional();
+
+        propose(&[], 0.0);
+        let u = uniform(&a, &mut rng);
+        let point = drawn();
+        assert_eq!(point.len(), 1);
+
+        propose(&point, 1.0);
+        assert!((uniform(&a, &mut rng) - u).abs() < 1e-12);
+        assert_ne!(uniform(&b, &mut rng), u);
+        propose(&point, 0.9);
+        assert_ne!(uniform(&a, &mut rng), u);
+        assert_eq!(drawn().len(), 1);
+    }
+}
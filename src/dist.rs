@@ -1,4 +1,10 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::Inspect;
+use crate::law::{Law, Shape};
+use crate::symbolic::{Expr, ToExpr};
+use crate::{design, graph, importance, Graph, Rng, Uncertain};
+use rand::distributions::{Open01, OpenClosed01};
+use rand_distr::StandardNormal;
+use std::any::{Any, TypeId};
 use std::marker::PhantomData;
 
 /// Wraps a [`Distribution`](rand::distributions::Distribution) and implements
@@ -13,6 +19,15 @@ use std::marker::PhantomData;
 ///
 /// See [`Uncertain::sample`](Uncertain::sample) for more details on the required semantics.
 ///
+/// Distributions of the [`Normal`](rand_distr::Normal), [`StandardNormal`],
+/// [`Open01`] and [`OpenClosed01`] families over `f64` are known in closed
+/// form, such that they can be analysed [symbolically](crate::Symbolic).
+/// They are sampled using the inverse of their cumulative distribution
+/// function, such that they follow [sampling designs](crate::Sampling) and
+/// [importance sampling](crate::Importance). Other families, including those
+/// whose parameters can not be read back, like [`Exp`](rand_distr::Exp), are
+/// sampled by `D` itself.
+///
 /// # Examples
 ///
 /// Basic usage:
@@ -29,28 +44,103 @@ where
     D: rand::distributions::Distribution<T>,
 {
     dist: D,
+    /// The distribution in closed form, if `D` is a known family.
+    law: Option<Law>,
     _p: PhantomData<T>,
 }
 
+/// The distribution of `dist` in closed form, if it is one
+/// of the families which are known to this crate.
+fn known<T, D>(dist: &D) -> Option<Law>
+where
+    T: 'static,
+    D: 'static,
+{
+    // All known families sample `f64` values.
+    if TypeId::of::<T>() != TypeId::of::<f64>() {
+        return None;
+    }
+    let dist: &dyn Any = dist;
+    let shape = if let Some(normal) = dist.downcast_ref::<rand_distr::Normal<f64>>() {
+        Shape::Normal {
+            mean: normal.mean(),
+            std_dev: normal.std_dev(),
+        }
+    } else if dist.is::<StandardNormal>() {
+        Shape::Normal {
+            mean: 0.0,
+            std_dev: 1.0,
+        }
+    } else if dist.is::<Open01>() || dist.is::<OpenClosed01>() {
+        Shape::Uniform {
+            low: 0.0,
+            high: 1.0,
+        }
+    } else {
+        return None;
+    };
+    Some(Law::new(shape))
+}
+
 impl<T, D> Uncertain for Distribution<T, D>
 where
     D: rand::distributions::Distribution<T>,
+    T: 'static,
 {
     type Value = T;
 
     fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
-        self.dist.sample(rng)
+        match self.law {
+            Some(law) => {
+                let value = importance::value()
+                    .unwrap_or_else(|| law.inverse_cdf(design::uniform(self, rng)));
+                // Known families always sample `f64` values, see `known`.
+                let mut value = Some(value);
+                let value: &mut dyn Any = &mut value;
+                value.downcast_mut::<Option<T>>().unwrap().take().unwrap()
+            }
+            None => self.dist.sample(rng),
+        }
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
     }
 }
 
-impl<T, D> From<D> for Distribution<T, D>
+impl<T, D> Inspect for Distribution<T, D>
 where
     D: rand::distributions::Distribution<T>,
+{
+    fn law(&self) -> Option<Law> {
+        self.law
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let name = graph::short_type_name(std::any::type_name::<D>());
+        graph.add(name, Vec::new())
+    }
+}
+
+impl<T, D> From<D> for Distribution<T, D>
+where
+    D: rand::distributions::Distribution<T> + 'static,
+    T: 'static,
 {
     fn from(dist: D) -> Self {
         Self {
+            law: known::<T, D>(&dist),
             dist,
             _p: PhantomData {},
         }
     }
 }
+
+impl<T, D> ToExpr for Distribution<T, D>
+where
+    D: rand::distributions::Distribution<T>,
+{
+    fn expr(&self) -> Expr {
+        Expr::source(self, self.law, "distribution")
+    }
+}
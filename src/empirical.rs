@@ -0,0 +1,227 @@
+use crate::inspect::{self, Inspect};
+use crate::symbolic::{Expr, ToExpr};
+use crate::{Graph, Rng, Uncertain};
+use rand::distributions::{Distribution, WeightedError, WeightedIndex};
+use rand::Rng as _;
+
+/// An uncertain value which resamples from a set of observations.
+///
+/// This is useful when the distribution of a value is not known in closed
+/// form, but historical measurements are available. Every sample
+/// picks one of the observations uniformly at random, or according to
+/// the given weights if the set was created using [`weighted`](Empirical::weighted).
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Empirical};
+///
+/// let rolls = Empirical::new(vec![1, 2, 3, 4, 5, 6]).unwrap();
+/// assert!(rolls.map(|roll| roll > 2).pr(0.6));
+/// ```
+pub struct Empirical<T>
+where
+    T: Clone,
+{
+    data: Vec<T>,
+    weights: Option<WeightedIndex<f64>>,
+}
+
+impl<T> Empirical<T>
+where
+    T: Clone,
+{
+    /// Create a new `Empirical` value which resamples
+    /// uniformly from the given observations.
+    ///
+    /// Returns an error if `data` is empty.
+    pub fn new(data: Vec<T>) -> Result<Self, WeightedError> {
+        if data.is_empty() {
+            return Err(WeightedError::NoItem);
+        }
+        Ok(Self {
+            data,
+            weights: None,
+        })
+    }
+
+    /// Create a new `Empirical` value which resamples from the
+    /// given observations, where each observation is picked with
+    /// probability proportional to its weight.
+    ///
+    /// Returns an error if there are no observations, or if the
+    /// weights are negative, not finite, or all zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Empirical};
+    ///
+    /// let biased_coin = Empirical::weighted(vec![(true, 3.0), (false, 1.0)]).unwrap();
+    /// assert!(biased_coin.pr(0.6));
+    /// assert!(!biased_coin.pr(0.9));
+    /// ```
+    pub fn weighted(data: Vec<(T, f64)>) -> Result<Self, WeightedError> {
+        let weights = WeightedIndex::new(data.iter().map(|(_, weight)| *weight))?;
+        Ok(Self {
+            data: data.into_iter().map(|(value, _)| value).collect(),
+            weights: Some(weights),
+        })
+    }
+
+    /// Turn a statistic over the observations into an uncertain value
+    /// using the [bootstrap][bootstrap].
+    ///
+    /// Every sample of the returned value draws a resample of the same
+    /// size as the observations (with replacement, respecting any weights) and
+    /// evaluates `statistic` on it. The result models the uncertainty of the
+    /// statistic due to the finite number of observations.
+    ///
+    /// [bootstrap]: https://en.wikipedia.org/wiki/Bootstrapping_(statistics)
+    ///
+    /// # Examples
+    ///
+    /// Estimate how certain we are about the mean of some measurements:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Empirical};
+    ///
+    /// let data = vec![4.9, 5.1, 5.0, 5.3, 4.8, 5.2, 4.7, 5.0];
+    /// let mean = Empirical::new(data)
+    ///     .unwrap()
+    ///     .bootstrap(|sample: &[f64]| sample.iter().sum::<f64>() / sample.len() as f64);
+    ///
+    /// assert!(mean.map(|mean| mean > 4.8 && mean < 5.2).pr(0.9));
+    /// ```
+    pub fn bootstrap<O, F>(self, statistic: F) -> Bootstrap<T, F>
+    where
+        F: Fn(&[T]) -> O,
+    {
+        Bootstrap {
+            empirical: self,
+            statistic,
+        }
+    }
+
+    fn pick(&self, rng: &mut Rng) -> &T {
+        let idx = match &self.weights {
+            Some(weights) => weights.sample(rng),
+            None => rng.gen_range(0..self.data.len()),
+        };
+        &self.data[idx]
+    }
+}
+
+impl<T> Uncertain for Empirical<T>
+where
+    T: Clone,
+{
+    type Value = T;
+
+    fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
+        self.pick(rng).clone()
+    }
+}
+
+/// An uncertain statistic computed by bootstrapping a set of observations.
+///
+/// See [`Empirical::bootstrap`].
+pub struct Bootstrap<T, F>
+where
+    T: Clone,
+{
+    empirical: Empirical<T>,
+    statistic: F,
+}
+
+impl<O, T, F> Uncertain for Bootstrap<T, F>
+where
+    T: Clone,
+    F: Fn(&[T]) -> O,
+{
+    type Value = O;
+
+    fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
+        let resample: Vec<T> = (0..self.empirical.data.len())
+            .map(|_| self.empirical.pick(rng).clone())
+            .collect();
+        (self.statistic)(&resample)
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<O, T, F> Inspect for Bootstrap<T, F>
+where
+    T: Clone,
+    F: Fn(&[T]) -> O,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let input = inspect::visit(&self.empirical, graph);
+        graph.add("bootstrap", vec![input])
+    }
+}
+
+impl<T> ToExpr for Empirical<T>
+where
+    T: Clone,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "empirical")
+    }
+}
+
+impl<O, T, F> ToExpr for Bootstrap<T, F>
+where
+    T: Clone,
+    F: Fn(&[T]) -> O,
+{
+    fn expr(&self) -> Expr {
+        Expr::opaque(self, "bootstrap")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg32;
+
+    #[test]
+    fn empirical_only_yields_observations() {
+        let data = vec![1, 7, 42];
+        let x = Empirical::new(data.clone()).unwrap();
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        for epoch in 0..100 {
+            assert!(data.contains(&x.sample(&mut rng, epoch)));
+        }
+    }
+
+    #[test]
+    fn empirical_rejects_bad_input() {
+        assert!(Empirical::<f64>::new(vec![]).is_err());
+        assert!(Empirical::weighted(vec![(1.0, 0.0), (2.0, 0.0)]).is_err());
+        assert!(Empirical::weighted(vec![(1.0, -1.0), (2.0, 2.0)]).is_err());
+    }
+
+    #[test]
+    fn weights_are_respected() {
+        let x = Empirical::weighted(vec![(0.0, 1.0), (10.0, 0.0)]).unwrap();
+        assert_eq!(x.expect(0.1).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn bootstrap_mean_is_close_to_sample_mean() {
+        let data: Vec<f64> = (0..50).map(|i| i as f64).collect();
+        let mean = Empirical::new(data)
+            .unwrap()
+            .bootstrap(|s: &[f64]| s.iter().sum::<f64>() / s.len() as f64);
+        let mu = mean.expect(0.5).unwrap();
+        assert!((mu - 24.5).abs() < 0.5);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{ConvergenceError, Limit, Sampling};
+use num_traits::Float;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Error returned by the fallible queries [`try_pr`](crate::Uncertain::try_pr)
+/// and [`try_expect`](crate::Uncertain::try_expect).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Error<F = f64>
+where
+    F: Float,
+{
+    /// The probability passed to the query was not in `(0, 1)`.
+    InvalidProbability(f32),
+    /// The precision passed to the query was not larger than `0`.
+    InvalidPrecision(F),
+    /// The result did not converge to the desired precision.
+    NonConvergence(ConvergenceError<F>),
+    /// The query was stopped early, because it exceeded its budget or was
+    /// cancelled. See [`Options`](crate::Options).
+    Interrupted(Interrupted<F>),
+    /// A sample was `NaN` or infinite, such that no result could be
+    /// computed. Contains the epoch of the sample. See [`NonFinite`](crate::NonFinite).
+    NonFinite {
+        /// The epoch in which the sample was taken.
+        epoch: usize,
+    },
+    /// An input drawn from a proposal does not have a known
+    /// distribution, see [`Importance`](crate::Importance).
+    UnknownDensity {
+        /// The name of the input.
+        input: String,
+    },
+    /// The sampling design can not be used, for example a Latin hypercube
+    /// without points, or a design with sources which can not follow it.
+    /// See [`Sampling`].
+    InvalidSampling(Sampling),
+}
+
+/// The partial state of a query which was stopped early, see
+/// [`Error::Interrupted`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Interrupted<F = f64>
+where
+    F: Float,
+{
+    pub(crate) limit: Limit,
+    pub(crate) samples: usize,
+    pub(crate) estimate: F,
+    pub(crate) std_error: F,
+}
+
+impl<F: Float> Interrupted<F> {
+    /// The state of a query which was stopped by `limit` after `samples` samples,
+    /// given its estimate and standard error. Queries which were stopped before
+    /// any estimate was available report a `NaN` estimate with an infinite error.
+    pub(crate) fn new(limit: Limit, samples: usize, estimate: Option<(F, F)>) -> Self {
+        let (estimate, std_error) = estimate.unwrap_or((F::nan(), F::infinity()));
+        Self {
+            limit,
+            samples,
+            estimate,
+            std_error,
+        }
+    }
+
+    /// The limit which caused the query to stop.
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    /// The number of samples taken before the query stopped.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// The estimate at the time the query stopped. For
+    /// [`pr_with`](crate::Uncertain::pr_with), this is the fraction
+    /// of samples which were `true`. If the query stopped before any
+    /// samples were taken, the estimate is `NaN` and its error infinite.
+    pub fn estimate(&self) -> F {
+        self.estimate
+    }
+
+    /// The two sigma confidence interval around the estimate,
+    /// see [`ConvergenceError::two_sigma_error`].
+    pub fn two_sigma_error(&self) -> F {
+        self.std_error + self.std_error
+    }
+}
+
+impl<F: Float + fmt::Display> fmt::Display for Error<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidProbability(probability) => {
+                write!(f, "Probability {:?} must be in (0, 1)", probability)
+            }
+            Error::InvalidPrecision(precision) => {
+                write!(f, "Precision {} must be larger than 0", precision)
+            }
+            Error::NonConvergence(err) => write!(f, "{}", err),
+            Error::Interrupted(partial) => write!(
+                f,
+                "Query was interrupted ({:?}) after {} samples with estimate {} +/- {}",
+                partial.limit,
+                partial.samples,
+                partial.estimate,
+                partial.two_sigma_error()
+            ),
+            Error::NonFinite { epoch } => {
+                write!(f, "Encountered non-finite sample in epoch {}", epoch)
+            }
+            Error::UnknownDensity { input } => {
+                write!(f, "Input `{}` does not have a known density", input)
+            }
+            Error::InvalidSampling(sampling) => {
+                write!(f, "Sampling {:?} can not be used", sampling)
+            }
+        }
+    }
+}
+
+impl<F: Float + fmt::Debug + fmt::Display> std::error::Error for Error<F> {}
+
+impl<F: Float> From<ConvergenceError<F>> for Error<F> {
+    fn from(err: ConvergenceError<F>) -> Self {
+        Error::NonConvergence(err)
+    }
+}
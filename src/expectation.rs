@@ -1,28 +1,63 @@
-use crate::Uncertain;
+use crate::error::Interrupted;
+use crate::{
+    common, context, design, Error as UncertainError, NonFinite, Options, Sampling, Uncertain,
+};
 use num_traits::{identities, Float};
+use rand::{Rng as _, SeedableRng};
 use rand_pcg::Pcg32;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt;
 
 const STEP: usize = 10;
 const MAXS: usize = 1000;
 
+/// The minimum number of used samples before a query tests for
+/// convergence, such that skipped samples can not leave an estimate
+/// whose error is computed from one or two samples.
+const MIN_SAMPLES: usize = STEP;
+
+/// The minimum number of samples used to estimate the coefficient
+/// of a control variate.
+const MIN_CONTROLLED_SAMPLES: usize = 100;
+
+/// The number of independent replicates of a sampling design.
+const REPLICATES: usize = 8;
+/// The number of points per replicate in the first round of
+/// sampling a design.
+const FIRST_ROUND: usize = 16;
+
 /// Information about a failed call to [`expect`](Uncertain::expect).
 ///
 /// This struct allows introspection of a failed attempt to calculate
 /// the expected value of an [`Uncertain`](Uncertain).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ConvergenceError<F>
 where
     F: Float,
 {
-    sample_mean: F,
-    diff_sum: F,
-    steps: F,
-    precision: F,
+    pub(crate) sample_mean: F,
+    pub(crate) std_error: F,
+    pub(crate) precision: F,
+    pub(crate) samples: usize,
+    pub(crate) non_finite: usize,
 }
 
 impl<F: Float> ConvergenceError<F> {
+    /// The error reported by [`expect`](Uncertain::expect) when sampling yields `NaN`.
+    pub(crate) fn nan(precision: F) -> Self {
+        Self {
+            sample_mean: F::nan(),
+            std_error: F::nan(),
+            precision,
+            samples: 0,
+            non_finite: 1,
+        }
+    }
+
     /// The expected value estimate obtained.
     ///
     /// This value is less precise than desired
@@ -46,8 +81,7 @@ impl<F: Float> ConvergenceError<F> {
     ///
     /// [iid]: https://en.wikipedia.org/wiki/Independent_and_identically_distributed_random_variables
     pub fn two_sigma_error(&self) -> F {
-        let std = mean_standard_deviation(self.diff_sum, self.steps);
-        std + std
+        self.std_error + self.std_error
     }
 
     /// The precision which was originally mandated by the
@@ -55,6 +89,17 @@ impl<F: Float> ConvergenceError<F> {
     pub fn desired_precision(&self) -> F {
         self.precision
     }
+
+    /// The number of samples which were used to compute the estimate.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// The number of samples which were `NaN` or infinite, see
+    /// [`NonFinite`](crate::NonFinite).
+    pub fn non_finite_samples(&self) -> usize {
+        self.non_finite
+    }
 }
 
 impl<F: Float + fmt::Display> fmt::Display for ConvergenceError<F> {
@@ -75,50 +120,927 @@ fn mean_standard_deviation<F: Float>(diff_sum: F, steps: F) -> F {
     diff_sum.sqrt() / steps // = sqrt( sigma^2 / n ) i.e. sqrt(var(E(x)))
 }
 
+/// The result of an estimate, see [`expect_with`](Uncertain::expect_with).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Report<F>
+where
+    F: Float,
+{
+    pub(crate) value: F,
+    pub(crate) std_error: F,
+    pub(crate) samples: usize,
+    pub(crate) non_finite: usize,
+}
+
+impl<F: Float> Report<F> {
+    /// The estimated value.
+    pub fn value(&self) -> F {
+        self.value
+    }
+
+    /// The two sigma confidence interval around the estimated value,
+    /// see [`ConvergenceError::two_sigma_error`].
+    pub fn two_sigma_error(&self) -> F {
+        self.std_error + self.std_error
+    }
+
+    /// The two sigma confidence interval relative to the estimated value.
+    pub fn relative_error(&self) -> F {
+        self.two_sigma_error() / self.value.abs()
+    }
+
+    /// The number of samples which were used to compute the estimate.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// The number of samples which were `NaN` or infinite.
+    pub fn non_finite_samples(&self) -> usize {
+        self.non_finite
+    }
+}
+
+/// Applies the policy for non-finite samples, returning
+/// if the sample should be used.
+pub(crate) fn check_finite<F: Float>(
+    sample: F,
+    epoch: usize,
+    options: &Options,
+    non_finite: &mut usize,
+) -> Result<bool, UncertainError<F>> {
+    if sample.is_finite() {
+        return Ok(true);
+    }
+    *non_finite += 1;
+    match options.non_finite {
+        NonFinite::Error => Err(UncertainError::NonFinite { epoch }),
+        NonFinite::Skip => Ok(false),
+        NonFinite::Propagate => Ok(true),
+    }
+}
+
 /// Compute the sample expectation.
-pub fn compute<U>(src: &U, precision: U::Value) -> Result<U::Value, ConvergenceError<U::Value>>
+pub fn compute<U>(
+    src: &U,
+    precision: U::Value,
+    options: &Options,
+) -> Result<Report<U::Value>, UncertainError<U::Value>>
 where
     U: Uncertain + ?Sized,
     U::Value: Float,
 {
-    let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+    match options.sampling {
+        Sampling::Random => (),
+        Sampling::Halton | Sampling::LatinHypercube { .. } => {
+            let mean = |samples: &[U::Value]| {
+                let sum = samples
+                    .iter()
+                    .fold(identities::zero(), |sum: U::Value, sample| sum + *sample);
+                (sum / cast(samples.len()), ())
+            };
+            return compute_replicated(src, precision, options, mean).map(|(report, _)| report);
+        }
+        Sampling::Antithetic => return compute_antithetic(src, precision, options),
+    }
+
+    let _frame = context::isolate();
+    let mut rng = options.rng();
 
     let mut sample_mean = identities::zero();
     let mut diff_sum = identities::zero();
     let mut steps = identities::zero();
+    let mut samples = 0;
+    let mut non_finite = 0;
+    let budget = options.budget();
 
     for batch in 0..MAXS {
         for batch_step in 0..STEP {
             let epoch = STEP * batch + batch_step;
+            if let Err(limit) = budget.check(epoch) {
+                return Err(UncertainError::Interrupted(Interrupted::new(
+                    limit,
+                    epoch,
+                    (samples > 0).then(|| (sample_mean, mean_standard_deviation(diff_sum, steps))),
+                )));
+            }
             let sample = src.sample(&mut rng, epoch);
+            if !check_finite(sample, epoch, options, &mut non_finite)? {
+                continue;
+            }
             let prev_sample_mean = sample_mean;
 
             // Using Welford's online algorithm:
             // https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance
             steps = steps + identities::one();
+            samples += 1;
             sample_mean = prev_sample_mean + (sample - prev_sample_mean) / steps;
             diff_sum = diff_sum + (sample - prev_sample_mean) * (sample - sample_mean);
         }
 
         let std = mean_standard_deviation(diff_sum, steps);
-        if std + std <= precision {
-            return Ok(sample_mean);
+        if (samples >= MIN_SAMPLES && std + std <= precision) || !sample_mean.is_finite() {
+            return Ok(Report {
+                value: sample_mean,
+                std_error: std,
+                samples,
+                non_finite,
+            });
         }
     }
 
-    Err(ConvergenceError {
+    Err(UncertainError::NonConvergence(ConvergenceError {
         sample_mean,
-        diff_sum,
-        steps,
+        std_error: mean_standard_deviation(diff_sum, steps),
+        precision,
+        samples,
+        non_finite,
+    }))
+}
+
+/// Compute the sample expectation from antithetic pairs of samples, where
+/// the sources of the second sample reflect the numbers drawn for the first.
+fn compute_antithetic<U>(
+    src: &U,
+    precision: U::Value,
+    options: &Options,
+) -> Result<Report<U::Value>, UncertainError<U::Value>>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Float,
+{
+    let _frame = context::isolate();
+    let mut rng = options.rng();
+    let _guard = design::antithetic();
+
+    let mut pair_mean = identities::zero();
+    let mut diff_sum = identities::zero();
+    let mut steps = identities::zero();
+    let mut samples = 0;
+    let mut non_finite = 0;
+    let budget = options.budget();
+    let two: U::Value = cast(2);
+
+    for batch in 0..MAXS / 2 {
+        for batch_step in 0..STEP {
+            let mut pair = [identities::zero(); 2];
+            let mut usable = true;
+            for (reflected, sample) in pair.iter_mut().enumerate() {
+                let epoch = 2 * (STEP * batch + batch_step) + reflected;
+                if let Err(limit) = budget.check(epoch) {
+                    return Err(UncertainError::Interrupted(Interrupted::new(
+                        limit,
+                        epoch,
+                        (samples > 0)
+                            .then(|| (pair_mean, mean_standard_deviation(diff_sum, steps))),
+                    )));
+                }
+                design::reflect(reflected == 1);
+                *sample = src.sample(&mut rng, epoch);
+                usable &= check_finite(*sample, epoch, options, &mut non_finite)?;
+            }
+            if !usable {
+                continue;
+            }
+
+            // The pairs are independent, such that Welford's algorithm
+            // can be applied to their means.
+            let sample = (pair[0] + pair[1]) / two;
+            let prev_pair_mean = pair_mean;
+            steps = steps + identities::one();
+            samples += 2;
+            pair_mean = prev_pair_mean + (sample - prev_pair_mean) / steps;
+            diff_sum = diff_sum + (sample - prev_pair_mean) * (sample - pair_mean);
+        }
+
+        let std = mean_standard_deviation(diff_sum, steps);
+        if (samples >= MIN_SAMPLES && std + std <= precision) || !pair_mean.is_finite() {
+            return Ok(Report {
+                value: pair_mean,
+                std_error: std,
+                samples,
+                non_finite,
+            });
+        }
+    }
+
+    Err(UncertainError::NonConvergence(ConvergenceError {
+        sample_mean: pair_mean,
+        std_error: mean_standard_deviation(diff_sum, steps),
+        precision,
+        samples,
+        non_finite,
+    }))
+}
+
+pub(crate) fn cast<F: Float>(value: usize) -> F {
+    F::from(value).unwrap_or_else(F::nan)
+}
+
+/// The result of a query, together with further details given by its statistic.
+type Detailed<F, T> = Result<(Report<F>, T), UncertainError<F>>;
+
+/// Estimate a statistic using independently randomized replicates of the
+/// sampling design given by the options. For Halton sequences, the number of
+/// points per replicate is doubled until the estimates of all replicates
+/// agree to the desired precision. Latin hypercube designs have a fixed
+/// number of points.
+///
+/// Besides the estimate, the statistic can give further details of every
+/// replicate, which are returned for the final round.
+pub(crate) fn compute_replicated<U, S, T>(
+    src: &U,
+    precision: U::Value,
+    options: &Options,
+    statistic: S,
+) -> Detailed<U::Value, Vec<T>>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Float,
+    S: Fn(&[U::Value]) -> (U::Value, T),
+{
+    let _frame = context::isolate();
+    let mut rng = options.rng();
+    let design_rng = Pcg32::seed_from_u64(rng.gen());
+    let (_guard, mut size, max_size) = match options.sampling {
+        Sampling::LatinHypercube { points } => {
+            if points == 0 || REPLICATES * points > STEP * MAXS {
+                return Err(UncertainError::InvalidSampling(options.sampling));
+            }
+            let guard = design::latin_hypercube(REPLICATES, points, design_rng);
+            (guard, points, points)
+        }
+        _ => {
+            let guard = design::halton(REPLICATES, design_rng);
+            (guard, FIRST_ROUND, STEP * MAXS / REPLICATES)
+        }
+    };
+
+    let mut replicates = vec![Vec::new(); REPLICATES];
+    let mut points = 0;
+    let mut epoch = 0;
+    let mut non_finite = 0;
+    let mut value = U::Value::nan();
+    let mut std_error = U::Value::infinity();
+    let budget = options.budget();
+
+    while size <= max_size {
+        for (replicate, samples) in replicates.iter_mut().enumerate() {
+            for index in points..size {
+                if let Err(limit) = budget.check(epoch) {
+                    return Err(UncertainError::Interrupted(Interrupted::new(
+                        limit,
+                        epoch,
+                        std_error.is_finite().then_some((value, std_error)),
+                    )));
+                }
+                design::select(replicate, index);
+                let drawn = rng.clone();
+                let sample = src.sample(&mut rng, epoch);
+                if rng != drawn {
+                    // A source bypassed the design and drew from the random
+                    // number generator, such that the design is not followed.
+                    return Err(UncertainError::InvalidSampling(options.sampling));
+                }
+                epoch += 1;
+                if !check_finite(sample, epoch - 1, options, &mut non_finite)? {
+                    continue;
+                }
+                if sample.is_nan() {
+                    let report = Report {
+                        value: sample,
+                        std_error: sample,
+                        samples: epoch,
+                        non_finite,
+                    };
+                    return Ok((report, Vec::new()));
+                }
+                samples.push(sample);
+            }
+        }
+        points = size;
+        size *= 2;
+        if replicates.iter().any(|samples| samples.is_empty()) {
+            continue;
+        }
+
+        let (estimates, details): (Vec<U::Value>, Vec<T>) =
+            replicates.iter().map(|samples| statistic(samples)).unzip();
+        let count: U::Value = cast(REPLICATES);
+        value = estimates
+            .iter()
+            .fold(identities::zero(), |sum: U::Value, estimate| {
+                sum + *estimate
+            })
+            / count;
+        let diff_sum = estimates
+            .iter()
+            .fold(identities::zero(), |sum: U::Value, estimate| {
+                sum + (*estimate - value) * (*estimate - value)
+            });
+        std_error = (diff_sum / (count - identities::one()) / count).sqrt();
+        if std_error + std_error <= precision || !value.is_finite() {
+            let report = Report {
+                value,
+                std_error,
+                samples: replicates.iter().map(Vec::len).sum(),
+                non_finite,
+            };
+            return Ok((report, details));
+        }
+    }
+
+    Err(UncertainError::NonConvergence(ConvergenceError {
+        sample_mean: value,
+        std_error,
         precision,
-    })
+        samples: replicates.iter().map(Vec::len).sum(),
+        non_finite,
+    }))
+}
+
+/// Which statistic of two uncertain values to compute.
+#[derive(Debug, Clone, Copy)]
+enum Statistic<F> {
+    Covariance,
+    Correlation,
+    /// The expectation of the first value, using the second value with
+    /// the given mean as a control variate.
+    ControlVariate {
+        mean: F,
+    },
+}
+
+/// The running means and (co-)moments of two uncertain values.
+struct Moments<F> {
+    mean_a: F,
+    mean_b: F,
+    diff_sum_a: F,
+    diff_sum_b: F,
+    co_diff_sum: F,
+    steps: F,
+}
+
+impl<F: Float> Moments<F> {
+    fn new() -> Self {
+        Self {
+            mean_a: F::zero(),
+            mean_b: F::zero(),
+            diff_sum_a: F::zero(),
+            diff_sum_b: F::zero(),
+            co_diff_sum: F::zero(),
+            steps: F::zero(),
+        }
+    }
+
+    fn push(&mut self, sample_a: F, sample_b: F) {
+        // Welford's algorithm, extended to the co-moment:
+        // https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Covariance
+        self.steps = self.steps + identities::one();
+        let delta_a = sample_a - self.mean_a;
+        let delta_b = sample_b - self.mean_b;
+        self.mean_a = self.mean_a + delta_a / self.steps;
+        self.mean_b = self.mean_b + delta_b / self.steps;
+        self.diff_sum_a = self.diff_sum_a + delta_a * (sample_a - self.mean_a);
+        self.diff_sum_b = self.diff_sum_b + delta_b * (sample_b - self.mean_b);
+        self.co_diff_sum = self.co_diff_sum + delta_a * (sample_b - self.mean_b);
+    }
+
+    /// The estimate of `statistic` and its standard error.
+    fn estimate(&self, statistic: Statistic<F>) -> (F, F) {
+        // The standard errors assume the values are approximately
+        // jointly normally distributed.
+        let steps = self.steps;
+        let var_a = self.diff_sum_a / steps;
+        let var_b = self.diff_sum_b / steps;
+        let cov = self.co_diff_sum / steps;
+        match statistic {
+            Statistic::Covariance => (cov, ((var_a * var_b + cov * cov) / steps).sqrt()),
+            Statistic::Correlation => {
+                let value = cov / (var_a * var_b).sqrt();
+                (value, (F::one() - value * value) / steps.sqrt())
+            }
+            Statistic::ControlVariate { mean } => {
+                // The optimal coefficient minimizes the variance of a - beta * b,
+                // which is what is left of the variance of a.
+                let beta = if var_b > F::zero() {
+                    cov / var_b
+                } else {
+                    F::zero()
+                };
+                let value = self.mean_a - beta * (self.mean_b - mean);
+                let std_error = if steps < cast(MIN_CONTROLLED_SAMPLES) {
+                    // Too few samples to trust the estimate of beta.
+                    F::infinity()
+                } else {
+                    ((var_a - beta * cov).max(F::zero()) / steps).sqrt()
+                };
+                (value, std_error)
+            }
+        }
+    }
+}
+
+/// Compute the sample covariance or correlation of two uncertain values,
+/// or the expectation of the first using the second as a control variate,
+/// sampled in the same epochs.
+fn compute_joint<A, B, F>(
+    a: &A,
+    b: &B,
+    precision: F,
+    options: &Options,
+    statistic: Statistic<F>,
+) -> Result<Report<F>, UncertainError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    if precision <= identities::zero() {
+        return Err(UncertainError::InvalidPrecision(precision));
+    }
+
+    let _frame = context::isolate();
+    let mut rng = options.rng();
+
+    let mut moments = Moments::new();
+    let mut samples = 0;
+    let mut non_finite = 0;
+
+    let mut value = F::nan();
+    let mut std_error = F::infinity();
+    let budget = options.budget();
+    for batch in 0..MAXS {
+        for batch_step in 0..STEP {
+            let epoch = STEP * batch + batch_step;
+            if let Err(limit) = budget.check(epoch) {
+                return Err(UncertainError::Interrupted(Interrupted::new(
+                    limit,
+                    epoch,
+                    (samples > 0).then(|| moments.estimate(statistic)),
+                )));
+            }
+            let sample_a = a.sample(&mut rng, epoch);
+            let sample_b = b.sample(&mut rng, epoch);
+            let worst = if sample_a.is_finite() {
+                sample_b
+            } else {
+                sample_a
+            };
+            if !check_finite(worst, epoch, options, &mut non_finite)? {
+                continue;
+            }
+            samples += 1;
+            moments.push(sample_a, sample_b);
+        }
+
+        (value, std_error) = moments.estimate(statistic);
+        let propagated = options.non_finite == NonFinite::Propagate && non_finite > 0;
+        if (samples >= MIN_SAMPLES && std_error + std_error <= precision) || propagated {
+            return Ok(Report {
+                value,
+                std_error,
+                samples,
+                non_finite,
+            });
+        }
+    }
+
+    Err(UncertainError::NonConvergence(ConvergenceError {
+        sample_mean: value,
+        std_error,
+        precision,
+        samples,
+        non_finite,
+    }))
+}
+
+/// Interpolate between the order statistics of sorted samples at
+/// the (fractional, zero based) `rank`.
+pub(crate) fn order_statistic<F: Float>(sorted: &[F], rank: f64) -> F {
+    let rank = rank.clamp(0.0, (sorted.len() - 1) as f64);
+    let (lower, upper) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+    let weight: F = F::from(rank.fract()).unwrap_or_else(F::zero);
+    lower + (upper - lower) * weight
+}
+
+/// Compute the sample quantile `q`, with a confidence interval
+/// derived from the order statistics of the samples.
+pub fn quantile<U>(
+    src: &U,
+    q: f64,
+    precision: U::Value,
+    options: &Options,
+) -> Result<Report<U::Value>, UncertainError<U::Value>>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Float,
+{
+    if matches!(
+        options.sampling,
+        Sampling::Halton | Sampling::LatinHypercube { .. }
+    ) {
+        let statistic = |samples: &[U::Value]| {
+            let sorted = sort(samples);
+            (order_statistic(&sorted, quantile_rank(q, sorted.len())), ())
+        };
+        return compute_replicated(src, precision, options, statistic).map(|(report, _)| report);
+    }
+
+    let statistic = |sorted: &[U::Value]| {
+        let (value, std_error) = sample_quantile(sorted, q);
+        (value, std_error, ())
+    };
+    compute_sorted(src, precision, options, statistic).map(|(report, _)| report)
+}
+
+/// The (fractional, zero based) rank of the quantile `q` within `n` sorted
+/// samples. Every sample stands for the same probability, with the samples
+/// at the midpoints, which suits both the stratified points of a design
+/// and independent samples.
+pub(crate) fn quantile_rank(q: f64, n: usize) -> f64 {
+    q * n as f64 - 0.5
+}
+
+/// A sorted copy of `samples`.
+pub(crate) fn sort<F: Float>(samples: &[F]) -> Vec<F> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    sorted
+}
+
+/// The quantile `q` of independent samples, and the standard
+/// error derived from the order statistics of the samples.
+pub(crate) fn sample_quantile<F: Float>(sorted: &[F], q: f64) -> (F, F) {
+    // The rank of the true quantile within the samples is approximately
+    // binomial, such that a confidence interval can be read off the samples
+    // two standard deviations of the rank away.
+    let n = sorted.len() as f64;
+    let rank = q * n;
+    let spread = 2.0 * (n * q * (1.0 - q)).sqrt();
+    let at = |rank: f64| sorted[(rank.max(0.0) as usize).min(sorted.len() - 1)];
+    let value = order_statistic(sorted, quantile_rank(q, sorted.len()));
+    if rank - spread < 0.0 || (rank + spread).ceil() >= n {
+        // The interval reaches beyond the samples, so there are too few of
+        // them to bound the error of an extreme quantile.
+        return (value, F::infinity());
+    }
+    let std_error = (at((rank + spread).ceil()) - at(rank - spread)) / cast(4);
+    (value, std_error)
+}
+
+/// Compute a statistic of independent samples, which are kept sorted. The
+/// statistic gives the estimate, its standard error and further details,
+/// and is computed after every batch of samples. The details of the final
+/// batch are returned.
+pub(crate) fn compute_sorted<U, S, T>(
+    src: &U,
+    precision: U::Value,
+    options: &Options,
+    statistic: S,
+) -> Detailed<U::Value, T>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Float,
+    S: Fn(&[U::Value]) -> (U::Value, U::Value, T),
+    T: Default,
+{
+    let _frame = context::isolate();
+    let mut rng = options.rng();
+
+    let mut sorted: Vec<U::Value> = Vec::new();
+    let mut value = U::Value::nan();
+    let mut std_error = U::Value::infinity();
+    let mut non_finite = 0;
+    let budget = options.budget();
+
+    for batch in 0..MAXS {
+        for batch_step in 0..STEP {
+            let epoch = STEP * batch + batch_step;
+            if let Err(limit) = budget.check(epoch) {
+                return Err(UncertainError::Interrupted(Interrupted::new(
+                    limit,
+                    epoch,
+                    (!sorted.is_empty()).then(|| {
+                        let (value, std_error, _) = statistic(&sorted);
+                        (value, std_error)
+                    }),
+                )));
+            }
+            let sample = src.sample(&mut rng, epoch);
+            if !check_finite(sample, epoch, options, &mut non_finite)? {
+                continue;
+            }
+            if sample.is_nan() {
+                let report = Report {
+                    value: sample,
+                    std_error: sample,
+                    samples: sorted.len() + 1,
+                    non_finite,
+                };
+                return Ok((report, T::default()));
+            }
+            let idx = sorted.partition_point(|other| *other <= sample);
+            sorted.insert(idx, sample);
+        }
+
+        if sorted.is_empty() {
+            continue;
+        }
+        let details;
+        (value, std_error, details) = statistic(&sorted);
+        if sorted.len() >= MIN_SAMPLES && std_error + std_error <= precision {
+            let report = Report {
+                value,
+                std_error,
+                samples: sorted.len(),
+                non_finite,
+            };
+            return Ok((report, details));
+        }
+    }
+
+    Err(UncertainError::NonConvergence(ConvergenceError {
+        sample_mean: value,
+        std_error,
+        precision,
+        samples: sorted.len(),
+        non_finite,
+    }))
+}
+
+/// Converts the result of a query to the result of [`expect`](Uncertain::expect),
+/// panicking if the precision was invalid.
+pub(crate) fn unwrap_precision<F: Float>(
+    result: Result<Report<F>, UncertainError<F>>,
+    precision: F,
+) -> Result<F, ConvergenceError<F>> {
+    match result {
+        Ok(report) => Ok(report.value),
+        Err(UncertainError::NonConvergence(err)) => Err(err),
+        Err(UncertainError::NonFinite { .. }) => Err(ConvergenceError::nan(precision)),
+        Err(_) => panic!("Precision must be larger than 0"),
+    }
+}
+
+/// Calculate the [covariance][covariance] of two uncertain values to
+/// the desired precision.
+///
+/// Both values are sampled in the same epochs, such that values which are
+/// shared using [`into_ref`](Uncertain::into_ref) take the same value
+/// in both. The convergence criterion is the same as for
+/// [`expect`](Uncertain::expect), where the error of the estimate is
+/// computed assuming the values are approximately jointly normally distributed.
+///
+/// [covariance]: https://en.wikipedia.org/wiki/Covariance
+///
+/// # Panics
+///
+/// Panics if `precision <= 0`.
+///
+/// # Examples
+///
+/// Basic usage: two outputs of the same model.
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, covariance};
+///
+/// let x = Measurement::normal(0.0, 1.0).unwrap().into_ref();
+/// let a = (&x).add(Measurement::normal(1.0, 1.0).unwrap());
+/// let b = (&x).sub(Measurement::normal(1.0, 1.0).unwrap());
+///
+/// let cov = covariance(&a, &b, 0.1).unwrap();
+/// assert!((cov - 1.0).abs() < 0.1);
+/// ```
+pub fn covariance<A, B, F>(a: &A, b: &B, precision: F) -> Result<F, ConvergenceError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    let result = compute_joint(a, b, precision, &Options::default(), Statistic::Covariance);
+    unwrap_precision(result, precision)
+}
+
+/// Calculate the covariance of two uncertain values to the desired precision,
+/// using the given options. This is the same as [`covariance`], but returns
+/// an error instead of panicking.
+///
+/// # Errors
+///
+/// See [`expect_with`](Uncertain::expect_with).
+pub fn covariance_with<A, B, F>(
+    a: &A,
+    b: &B,
+    precision: F,
+    options: &Options,
+) -> Result<Report<F>, UncertainError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    compute_joint(a, b, precision, options, Statistic::Covariance)
+}
+
+/// Calculate the [Pearson correlation coefficient][correlation] of two
+/// uncertain values to the desired precision.
+///
+/// See [`covariance`] for details.
+///
+/// [correlation]: https://en.wikipedia.org/wiki/Pearson_correlation_coefficient
+///
+/// # Panics
+///
+/// Panics if `precision <= 0`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, correlation};
+///
+/// let x = Measurement::normal(0.0, 1.0).unwrap().into_ref();
+/// let a = (&x).add(Measurement::normal(1.0, 1.0).unwrap());
+///
+/// let rho = correlation(&a, &x, 0.05).unwrap();
+/// assert!((rho - 0.5f64.sqrt()).abs() < 0.05);
+/// ```
+pub fn correlation<A, B, F>(a: &A, b: &B, precision: F) -> Result<F, ConvergenceError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    let result = compute_joint(a, b, precision, &Options::default(), Statistic::Correlation);
+    unwrap_precision(result, precision)
+}
+
+/// Calculate the correlation of two uncertain values to the desired precision,
+/// using the given options. This is the same as [`correlation`], but returns
+/// an error instead of panicking.
+///
+/// # Errors
+///
+/// See [`expect_with`](Uncertain::expect_with).
+pub fn correlation_with<A, B, F>(
+    a: &A,
+    b: &B,
+    precision: F,
+    options: &Options,
+) -> Result<Report<F>, UncertainError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    compute_joint(a, b, precision, options, Statistic::Correlation)
+}
+
+/// Calculate the expectation of an uncertain value to the desired precision,
+/// using a [control variate][cv] with known mean to reduce the number of samples needed.
+///
+/// Both values are sampled in the same epochs, such that the control can be
+/// derived from the inputs of `value` by sharing them using
+/// [`into_ref`](Uncertain::into_ref). The better the control is correlated with
+/// `value`, the fewer samples are needed. The convergence criterion is the same
+/// as for [`expect`](Uncertain::expect), but uses the reduced variance of the
+/// controlled estimate.
+///
+/// [cv]: https://en.wikipedia.org/wiki/Control_variates
+///
+/// # Errors
+///
+/// See [`expect_with`](Uncertain::expect_with).
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, Options, expect_with_control};
+///
+/// let x = Measurement::normal(1.0, 1.0).unwrap().into_ref();
+/// let value = (&x).exp();
+/// // The first terms of the Taylor series of exp(x) have a known mean
+/// let control = (&x).map(|x| 1.0 + x + x * x / 2.0);
+///
+/// let report = expect_with_control(&value, &control, 3.0, 0.1, &Options::new()).unwrap();
+/// assert!((report.value() - 1.5f64.exp()).abs() < 0.1);
+/// ```
+pub fn expect_with_control<U, C, F>(
+    value: &U,
+    control: &C,
+    control_mean: F,
+    precision: F,
+    options: &Options,
+) -> Result<Report<F>, UncertainError<F>>
+where
+    U: Uncertain<Value = F> + ?Sized,
+    C: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    let statistic = Statistic::ControlVariate { mean: control_mean };
+    compute_joint(value, control, precision, options, statistic)
+}
+
+/// The difference of two values, where inputs with the
+/// same name draw the same random numbers in both.
+struct Paired<'a, A: ?Sized, B: ?Sized> {
+    a: &'a A,
+    b: &'a B,
+}
+
+impl<A, B, F> Uncertain for Paired<'_, A, B>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    type Value = F;
+
+    fn sample(&self, rng: &mut crate::Rng, epoch: usize) -> F {
+        let _guard = common::share(rng);
+        self.a.sample(rng, epoch) - self.b.sample(rng, epoch)
+    }
+}
+
+/// Calculate the difference `E(a) - E(b)` of the expectations of two
+/// uncertain values to the desired precision, using [common random numbers][crn].
+///
+/// Both values are sampled in the same epochs, and inputs which are
+/// [`named`](Uncertain::named) the same draw the same random numbers in
+/// both, even if they are separate nodes. This makes it possible to compare
+/// two variants of a model which are built from the same inputs. The
+/// convergence criterion is the same as for [`expect`](Uncertain::expect),
+/// but applies to the paired differences, whose variance is much smaller
+/// than that of independent samples if the variants are similar.
+///
+/// [crn]: https://en.wikipedia.org/wiki/Variance_reduction#Common_Random_Numbers_(CRN)
+///
+/// # Panics
+///
+/// Panics if `precision <= 0`.
+///
+/// # Examples
+///
+/// Basic usage: the effect of a small speed-up on a noisy latency.
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, compare};
+///
+/// let latency = |speedup: f64| {
+///     let load = Measurement::normal(10.0, 3.0).unwrap().named("load");
+///     let network = Measurement::uniform(0.0, 5.0).unwrap().named("network");
+///     load.map(move |load| load / speedup).add(network)
+/// };
+///
+/// let diff = compare(&latency(1.0), &latency(1.1), 0.05).unwrap();
+/// assert!((diff - (10.0 - 10.0 / 1.1)).abs() < 0.05);
+/// ```
+pub fn compare<A, B, F>(a: &A, b: &B, precision: F) -> Result<F, ConvergenceError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    let result = compare_with(a, b, precision, &Options::default());
+    unwrap_precision(result, precision)
+}
+
+/// Calculate the difference of the expectations of two uncertain values to
+/// the desired precision, using the given options. This is the same as
+/// [`compare`], but returns an error instead of panicking.
+///
+/// The [`two_sigma_error`](Report::two_sigma_error) of the report is the
+/// paired confidence interval around the difference. Since sampling designs
+/// assign numbers to sources rather than to names, the values are always
+/// sampled randomly, regardless of [`Options::sampling`].
+///
+/// # Errors
+///
+/// See [`expect_with`](Uncertain::expect_with).
+pub fn compare_with<A, B, F>(
+    a: &A,
+    b: &B,
+    precision: F,
+    options: &Options,
+) -> Result<Report<F>, UncertainError<F>>
+where
+    A: Uncertain<Value = F> + ?Sized,
+    B: Uncertain<Value = F> + ?Sized,
+    F: Float,
+{
+    let options = options.clone().sampling(Sampling::Random);
+    compute(&Paired { a, b }, precision, &options)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Distribution;
-    use rand_distr::Normal;
+    use crate::{Distribution, Measurement};
+    use rand_distr::{Exp, Normal};
 
     #[test]
     fn simple_expectation() {
@@ -126,9 +1048,9 @@ mod tests {
         for val in values {
             let x = Distribution::from(Normal::new(val, 1.0).unwrap());
 
-            let mu = compute(&x, 0.1);
+            let mu = compute(&x, 0.1, &Options::default());
             assert!(mu.is_ok());
-            assert!((mu.unwrap() - val).abs() < 0.1);
+            assert!((mu.unwrap().value() - val).abs() < 0.1);
         }
     }
 
@@ -136,13 +1058,279 @@ mod tests {
     fn failed_expectation() {
         let x = Distribution::from(Normal::new(0.0, 1000.0).unwrap());
 
-        let mu = compute(&x, 0.1);
-        assert!(mu.is_err());
-        assert!(mu.err().unwrap().two_sigma_error() > 0.1);
+        match compute(&x, 0.1, &Options::default()) {
+            Err(UncertainError::NonConvergence(err)) => assert!(err.two_sigma_error() > 0.1),
+            _ => panic!("expectation should not converge"),
+        }
 
-        let mu = compute(&x, 100.0);
+        let mu = compute(&x, 100.0, &Options::default());
         assert!(mu.is_ok());
-        assert!(mu.unwrap().abs() < 100.0);
+        assert!(mu.unwrap().value().abs() < 100.0);
+    }
+
+    #[test]
+    fn non_finite_policies() {
+        let x = Distribution::from(Normal::new(0.0, 1.0).unwrap()).map(|v: f64| v.sqrt());
+        assert!(matches!(
+            compute(&x, 0.1, &Options::default()),
+            Err(UncertainError::NonFinite { .. })
+        ));
+        assert!(x.expect(0.1).unwrap_err().non_converged_value().is_nan());
+
+        // E(sqrt(x) | x >= 0) = 0.8222 (to four digits)
+        let options = Options::new().non_finite(NonFinite::Skip);
+        let report = compute(&x, 0.05, &options).unwrap();
+        assert!((report.value() - 0.8222).abs() < 0.05);
+        assert!(report.non_finite_samples() > report.samples() / 2);
+
+        let options = Options::new().non_finite(NonFinite::Propagate);
+        let report = compute(&x, 0.05, &options).unwrap();
+        assert!(report.value().is_nan());
+        assert!(report.non_finite_samples() > 0);
+
+        let y = Distribution::from(Normal::new(0.0, 1.0).unwrap());
+        let report = covariance_with(&x, &y, 0.05, &options).unwrap();
+        assert!(report.value().is_nan());
+    }
+
+    #[test]
+    fn skipped_samples_do_not_converge_early() {
+        let x = Measurement::uniform(0.0, 1.0).unwrap().map(|u: f64| {
+            if u < 0.9 {
+                f64::NAN
+            } else {
+                1000.0 * u
+            }
+        });
+        for seed in 0..50 {
+            let options = Options::new().non_finite(NonFinite::Skip).seed(seed);
+            if let Ok(report) = compute(&x, 1.0, &options) {
+                assert!(report.samples() >= MIN_SAMPLES);
+                assert!(report.two_sigma_error() > 0.0);
+            }
+            let y = Measurement::normal(0.0, 1.0).unwrap();
+            if let Ok(report) = covariance_with(&x, &y, 1.0, &options) {
+                assert!(report.samples() >= MIN_SAMPLES);
+            }
+        }
+    }
+
+    #[test]
+    fn budgets_return_partial_state() {
+        let x = Distribution::from(Normal::new(2.0, 1.0).unwrap());
+        let options = Options::new().max_samples(100);
+        match compute(&x, 0.001, &options) {
+            Err(UncertainError::Interrupted(partial)) => {
+                assert_eq!(partial.samples(), 100);
+                assert!((partial.estimate() - 2.0).abs() < partial.two_sigma_error() * 2.0);
+            }
+            _ => panic!("expectation should have been interrupted"),
+        }
+
+        let options = Options::new().max_duration(std::time::Duration::from_secs(0));
+        assert!(matches!(
+            compute(&x, 0.001, &options),
+            Err(UncertainError::Interrupted(_))
+        ));
+    }
+
+    #[test]
+    fn budgets_within_a_batch_return_partial_estimates() {
+        let x = Distribution::from(Normal::new(2.0, 1.0).unwrap()).into_ref();
+        let y = (&x).map(|x| 2.0 * x);
+        let options = Options::new().max_samples(5);
+        let partial = |result: Result<Report<f64>, UncertainError<f64>>| match result {
+            Err(UncertainError::Interrupted(partial)) => {
+                assert_eq!(partial.samples(), 5);
+                partial.estimate()
+            }
+            _ => panic!("query should have been interrupted"),
+        };
+        assert!(partial(covariance_with(&x, &y, 0.001, &options)) > 0.0);
+        assert!((partial(correlation_with(&x, &y, 0.001, &options)) - 1.0).abs() < 1e-9);
+        let estimate = partial(quantile(&x, 0.5, 0.001, &options));
+        assert!((estimate - 2.0).abs() < 3.0);
+    }
+
+    #[test]
+    fn budgets_without_samples_have_no_estimate() {
+        let x = Distribution::from(Normal::new(2.0, 1.0).unwrap());
+        let no_estimate = |result: Result<Report<f64>, UncertainError<f64>>| match result {
+            Err(UncertainError::Interrupted(partial)) => {
+                assert_eq!(partial.samples(), 0);
+                assert!(partial.estimate().is_nan());
+                assert_eq!(partial.two_sigma_error(), f64::INFINITY);
+            }
+            _ => panic!("query should have been interrupted"),
+        };
+        for sampling in [
+            Sampling::Random,
+            Sampling::Halton,
+            Sampling::Antithetic,
+            Sampling::LatinHypercube { points: 100 },
+        ] {
+            let options = Options::new().max_samples(0).sampling(sampling);
+            no_estimate(compute(&x, 0.1, &options));
+            no_estimate(quantile(&x, 0.5, 0.1, &options));
+        }
+        let options = Options::new().max_samples(0);
+        no_estimate(covariance_with(&x, &x, 0.1, &options));
+    }
+
+    #[test]
+    fn quantiles_of_normal_distribution() {
+        let x = Distribution::from(Normal::new(1.0, 2.0).unwrap());
+        // The 0.975 quantile of the standard normal is 1.96.
+        for (q, expected) in [
+            (0.5, 1.0),
+            (0.975, 1.0 + 2.0 * 1.96),
+            (0.1, 1.0 - 2.0 * 1.2816),
+        ] {
+            let report = quantile(&x, q, 0.2, &Options::default()).unwrap();
+            assert!((report.value() - expected).abs() < 0.2, "{}", q);
+        }
+
+        let options = Options::new().seed(42);
+        let report = quantile(&x, 0.5, 0.2, &options).unwrap();
+        assert!((report.value() - 1.0).abs() < 0.4);
+        assert!(report.samples() > 100);
+    }
+
+    #[test]
+    fn halton_design_needs_fewer_samples() {
+        let model = || {
+            let x = Measurement::normal(1.0, 1.0).unwrap();
+            let y = Measurement::uniform(0.0, 2.0).unwrap().into_shared();
+            x.add(y.clone().mul(y))
+        };
+        let random = compute(&model(), 0.02, &Options::default());
+        let options = Options::new().sampling(Sampling::Halton);
+        let report = compute(&model(), 0.02, &options).unwrap();
+        // E(x + y^2) = 1 + 4 / 3
+        assert!((report.value() - 7.0 / 3.0).abs() < 0.02);
+        match random {
+            Ok(random) => assert!(report.samples() < random.samples()),
+            Err(err) => assert!(matches!(err, UncertainError::NonConvergence(_))),
+        }
+
+        let x = Measurement::normal(1.0, 2.0).unwrap();
+        let report = quantile(&x, 0.975, 0.05, &options).unwrap();
+        assert!((report.value() - (1.0 + 2.0 * 1.96)).abs() < 0.05);
+    }
+
+    #[test]
+    fn designs_reject_sources_which_bypass_them() {
+        let x = Measurement::normal(1.0, 1.0)
+            .unwrap()
+            .add(Distribution::from(Exp::new(1.0).unwrap()));
+        for sampling in [Sampling::Halton, Sampling::LatinHypercube { points: 100 }] {
+            let options = Options::new().sampling(sampling);
+            assert!(matches!(
+                compute(&x, 0.05, &options),
+                Err(UncertainError::InvalidSampling(_))
+            ));
+        }
+
+        let covariance = vec![vec![1.0, 0.5], vec![0.5, 1.0]];
+        let x = crate::MultivariateNormal::new(vec![1.0, 2.0], covariance)
+            .unwrap()
+            .map(|x| x[0] + x[1]);
+        let options = Options::new().sampling(Sampling::Halton);
+        let report = compute(&x, 0.05, &options).unwrap();
+        assert!((report.value() - 3.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn designs_drive_known_distributions() {
+        let x = Distribution::from(Normal::new(1.0, 2.0).unwrap())
+            .add(Measurement::uniform(0.0, 2.0).unwrap());
+        for sampling in [Sampling::Halton, Sampling::LatinHypercube { points: 200 }] {
+            let options = Options::new().sampling(sampling);
+            let report = compute(&x, 0.1, &options).unwrap();
+            assert!((report.value() - 2.0).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn nested_queries_keep_the_outer_design() {
+        let options = Options::new().sampling(Sampling::Halton);
+        let inner = || {
+            let x = Measurement::normal(0.0, 1.0).unwrap();
+            let options = Options::new().sampling(Sampling::Halton);
+            compute(&x, 0.1, &options).unwrap().value()
+        };
+        let x = Measurement::normal(1.0, 1.0)
+            .unwrap()
+            .map(move |x| x + inner());
+        let report = compute(&x, 0.1, &options).unwrap();
+        // the inner estimate is the same in every sample
+        assert!((report.value() - (1.0 + inner())).abs() < 0.1);
+    }
+
+    #[test]
+    fn latin_hypercube_estimates_use_whole_design() {
+        let x = Measurement::normal(1.0, 1.0)
+            .unwrap()
+            .add(Measurement::uniform(0.0, 2.0).unwrap());
+        let options = Options::new().sampling(Sampling::LatinHypercube { points: 50 });
+        let report = compute(&x, 0.05, &options).unwrap();
+        assert!((report.value() - 2.0).abs() < 0.05);
+        assert_eq!(report.samples(), REPLICATES * 50);
+
+        let report = quantile(&x, 0.5, 0.2, &options).unwrap();
+        assert!((report.value() - 2.0).abs() < 0.2);
+
+        let options = Options::new().sampling(Sampling::LatinHypercube { points: 2 });
+        assert!(matches!(
+            compute(&x, 0.05, &options),
+            Err(UncertainError::NonConvergence(_))
+        ));
+
+        for points in [0, STEP * MAXS / REPLICATES + 1] {
+            let options = Options::new().sampling(Sampling::LatinHypercube { points });
+            assert!(matches!(
+                compute(&x, 0.05, &options),
+                Err(UncertainError::InvalidSampling(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn variance_reduction_needs_fewer_samples() {
+        let x = Measurement::normal(0.0, 0.5).unwrap().into_ref();
+        let value = (&x).exp();
+        let expected = 0.125f64.exp();
+        let random = compute(&value, 0.02, &Options::default()).unwrap();
+
+        let options = Options::new().sampling(Sampling::Antithetic);
+        let antithetic = compute(&value, 0.02, &options).unwrap();
+        assert!((antithetic.value() - expected).abs() < 0.02);
+        assert!(antithetic.samples() < random.samples());
+
+        // The first terms of the Taylor series of exp(x)
+        let control = (&x).map(|x| 1.0 + x + x * x / 2.0);
+        let controlled =
+            expect_with_control(&value, &control, 1.125, 0.02, &Options::default()).unwrap();
+        assert!((controlled.value() - expected).abs() < 0.02);
+        assert!(controlled.samples() < random.samples());
+    }
+
+    #[test]
+    fn joint_statistics_respect_shared_values() {
+        let x = Distribution::from(Normal::new(3.0, 2.0).unwrap()).into_ref();
+        let y = Distribution::from(Normal::new(-1.0, 1.0).unwrap());
+
+        let cov = covariance(&&x, &&x, 0.2).unwrap();
+        assert!((cov - 4.0).abs() < 0.2);
+        let cov = covariance(&&x, &y, 0.1).unwrap();
+        assert!(cov.abs() < 0.1);
+
+        let neg = (&x).map(|v| -2.0 * v);
+        let rho = correlation(&&x, &neg, 0.01).unwrap();
+        assert!((rho + 1.0).abs() < 0.01);
+
+        let err = covariance(&&x, &y, 0.0001).unwrap_err();
+        assert!(err.two_sigma_error() > err.desired_precision());
     }
 
     #[test]
@@ -179,4 +1367,19 @@ mod tests {
             assert!(err.two_sigma_error() > err.desired_precision());
         }
     }
+
+    #[test]
+    fn paired_comparison_needs_fewer_samples() {
+        let model = |offset: f64| {
+            let x = Measurement::normal(0.0, 1.0).unwrap().named("x");
+            x.map(move |x| x + offset)
+        };
+        let (a, b) = (model(0.1), model(0.0));
+        let report = compare_with(&a, &b, 0.01, &Options::new()).unwrap();
+        assert!((report.value() - 0.1).abs() < 1e-9);
+        assert_eq!(report.samples(), STEP);
+
+        let independent = compute(&a.sub(b), 0.05, &Options::new()).unwrap();
+        assert!(independent.samples() > 100 * STEP);
+    }
 }
@@ -0,0 +1,184 @@
+//! Introspection of the computation graph of uncertain values.
+
+use std::fmt::Write;
+
+/// Remove module paths from a type name, e.g. `alloc::vec::Vec<f64>`
+/// becomes `Vec<f64>`.
+pub(crate) fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment = 0;
+    for c in name.chars() {
+        if c == ':' {
+            short.truncate(segment);
+        } else {
+            short.push(c);
+            if !(c.is_alphanumeric() || c == '_') {
+                segment = short.len();
+            }
+        }
+    }
+    short
+}
+
+/// A node in a [`Graph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    label: String,
+    inputs: Vec<usize>,
+}
+
+impl Node {
+    /// A short description of the node, e.g. `"+"`, `"map"` or
+    /// the name given using [`named`](crate::Uncertain::named).
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The indices of the nodes this node is computed from.
+    pub fn inputs(&self) -> &[usize] {
+        &self.inputs
+    }
+}
+
+/// The computation graph of an uncertain value, as returned
+/// by [`describe`](crate::Uncertain::describe).
+///
+/// The graph is a [directed acyclic graph][dag] where every node stores the
+/// indices of its inputs. Nodes are ordered such that the inputs of a node
+/// always come before the node itself, and the last node is the value
+/// which was described. Shared values (see [`into_ref`](crate::Uncertain::into_ref))
+/// appear only once, and are an input to every node which uses them.
+///
+/// [dag]: https://en.wikipedia.org/wiki/Directed_acyclic_graph
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement};
+///
+/// let x = Measurement::normal(5.0, 2.0).unwrap().named("x").into_ref();
+/// let y = (&x).mul(&x).add(&x);
+///
+/// let graph = y.describe();
+/// let labels: Vec<&str> = graph.nodes().iter().map(|node| node.label()).collect();
+/// assert_eq!(labels, vec!["normal(5, 2)", "x", "*", "+"]);
+/// assert_eq!(graph.nodes()[3].inputs(), &[2, 1]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    shared: Vec<(usize, usize)>,
+}
+
+impl Graph {
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            shared: Vec::new(),
+        }
+    }
+
+    /// Add a node with the given inputs, returning its index.
+    pub(crate) fn add<S: Into<String>>(&mut self, label: S, inputs: Vec<usize>) -> usize {
+        self.nodes.push(Node {
+            label: label.into(),
+            inputs,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Add the nodes of a shared value located at `address` only
+    /// once, returning the index of its output node.
+    pub(crate) fn shared<F: FnOnce(&mut Self) -> usize>(
+        &mut self,
+        address: usize,
+        visit: F,
+    ) -> usize {
+        if let Some((_, idx)) = self.shared.iter().find(|(other, _)| *other == address) {
+            return *idx;
+        }
+        let idx = visit(self);
+        self.shared.push((address, idx));
+        idx
+    }
+
+    /// All nodes in the graph, such that the inputs of every node come
+    /// before the node itself.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Render the graph in the [DOT language][dot], which can be used
+    /// to visualize it using [Graphviz](https://graphviz.org).
+    ///
+    /// [dot]: https://graphviz.org/doc/info/lang.html
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement, PointMass};
+    ///
+    /// let x = Measurement::uniform(0.0, 1.0).unwrap().named("speed");
+    /// let y = x.gt(PointMass::new(0.5));
+    /// assert_eq!(y.describe().to_dot(), r#"digraph {
+    ///     0 [label="uniform(0, 1)"];
+    ///     1 [label="speed"];
+    ///     2 [label="PointMass<f64>"];
+    ///     3 [label=">"];
+    ///     0 -> 1;
+    ///     1 -> 3;
+    ///     2 -> 3;
+    /// }
+    /// "#);
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let label = node.label.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(dot, "    {} [label=\"{}\"];", idx, label).unwrap();
+        }
+        for (idx, node) in self.nodes.iter().enumerate() {
+            for input in node.inputs.iter() {
+                writeln!(dot, "    {} -> {};", input, idx).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Distribution, Measurement, Uncertain};
+    use rand_distr::Normal;
+
+    #[test]
+    fn type_names_are_shortened() {
+        assert_eq!(short_type_name("alloc::vec::Vec<f64>"), "Vec<f64>");
+        assert_eq!(
+            short_type_name("a::B<c::D, (e::F, [g::H; 3])>"),
+            "B<D, (F, [H; 3])>"
+        );
+    }
+
+    #[test]
+    fn shared_nodes_appear_once() {
+        let x = Distribution::from(Normal::new(0.0, 1.0).unwrap()).into_ref();
+        let y = Measurement::normal(1.0, 1.0).unwrap();
+        let z = (&x).add(y).join(&x, |a, b| a * b).map(|v| v > 0.0);
+
+        let graph = z.describe();
+        let labels: Vec<&str> = graph.nodes().iter().map(|node| node.label()).collect();
+        assert_eq!(
+            labels,
+            vec!["Normal<f64>", "normal(1, 1)", "+", "join", "map"]
+        );
+        assert_eq!(graph.nodes()[3].inputs(), &[2, 0]);
+        assert_eq!(graph.nodes()[4].inputs(), &[3]);
+    }
+}
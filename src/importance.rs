@@ -0,0 +1,326 @@
+//! Importance sampling of named inputs.
+
+use crate::context::{self, Context};
+use crate::{design, expectation, Density, Error, Options, Report, Rng, Uncertain};
+use num_traits::Float;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+
+type Proposals = Arc<[(String, Arc<dyn Density + Send + Sync>)]>;
+
+/// The proposals of the query which is currently being computed, and
+/// the likelihood ratio of the current sample.
+pub(crate) struct Weights {
+    proposals: Proposals,
+    log_weight: f64,
+    pending: Option<f64>,
+    unknown: Option<String>,
+    /// The inputs which are already weighted, if named inputs are shared.
+    weighted: Vec<String>,
+}
+
+fn slot(ctx: &mut Context) -> &mut Option<Weights> {
+    &mut ctx.importance
+}
+
+/// If the named input has a proposal, draws its next value from the proposal
+/// and returns `true`. The value is then used by the next source which
+/// samples from a known distribution, see [`value`].
+///
+/// `target` gives the density of the input. If it is not known, the input
+/// is recorded as having no density and sampled from its own distribution.
+/// If named inputs are shared, see [`common`](crate::common), every name
+/// is a single input whose likelihood ratio is only applied once.
+pub(crate) fn input<T>(name: &str, target: T, rng: &mut Rng) -> bool
+where
+    T: FnOnce() -> Option<Arc<dyn Density + Send + Sync>>,
+{
+    let found = context::with(|ctx| {
+        let shared = ctx.salt.is_some();
+        let weights = ctx.importance.as_ref()?;
+        let (_, proposal) = weights.proposals.iter().find(|(other, _)| other == name)?;
+        let weighted = shared && weights.weighted.iter().any(|other| other == name);
+        Some((proposal.clone(), weighted))
+    });
+    let (proposal, weighted) = match found {
+        Some(found) => found,
+        None => return false,
+    };
+    let target = match target() {
+        Some(target) => target,
+        None => {
+            context::with(|ctx| {
+                let weights = ctx.importance.as_mut().unwrap();
+                weights.unknown.get_or_insert_with(|| name.to_string());
+            });
+            return false;
+        }
+    };
+    let value = proposal.inverse_cdf(design::uniform(&*proposal, rng));
+    context::with(|ctx| {
+        let weights = ctx.importance.as_mut().unwrap();
+        if !weighted {
+            weights.log_weight += target.log_pdf(value) - proposal.log_pdf(value);
+            weights.weighted.push(name.to_string());
+        }
+        weights.pending = Some(value);
+    });
+    true
+}
+
+/// Called after sampling an input for which [`input`] returned `true`,
+/// to check that its value was used.
+pub(crate) fn finish(name: &str) {
+    context::with(|ctx| {
+        if let Some(weights) = ctx.importance.as_mut() {
+            if weights.pending.take().is_some() {
+                weights.unknown.get_or_insert_with(|| name.to_string());
+            }
+        }
+    })
+}
+
+/// The value drawn from the proposal for the input which is currently
+/// being sampled, which replaces the sample of its source.
+pub(crate) fn value() -> Option<f64> {
+    context::with(|ctx| ctx.importance.as_mut()?.pending.take())
+}
+
+/// A value whose samples are multiplied by their likelihood ratio.
+struct Weighted<'a, U: ?Sized, M> {
+    uncertain: &'a U,
+    to_float: M,
+    proposals: Proposals,
+    /// The first input with a proposal but without a known density.
+    unknown: RefCell<Option<String>>,
+}
+
+impl<U, M, F> Uncertain for Weighted<'_, U, M>
+where
+    U: Uncertain + ?Sized,
+    M: Fn(U::Value) -> F,
+    F: Float,
+{
+    type Value = F;
+
+    fn sample(&self, rng: &mut Rng, epoch: usize) -> F {
+        let weights = Weights {
+            proposals: self.proposals.clone(),
+            log_weight: 0.0,
+            pending: None,
+            unknown: None,
+            weighted: Vec::new(),
+        };
+        let _guard = context::enter(slot, weights);
+        let value = (self.to_float)(self.uncertain.sample(rng, epoch));
+        let weights = context::with(|ctx| ctx.importance.take()).unwrap();
+        if let Some(input) = weights.unknown {
+            self.unknown.borrow_mut().get_or_insert(input);
+            return F::nan();
+        }
+        let weight = F::from(weights.log_weight.exp()).unwrap_or_else(F::nan);
+        if weight.is_zero() {
+            // the value may be undefined outside the support of the input
+            weight
+        } else {
+            value * weight
+        }
+    }
+}
+
+/// Compute expectations and probabilities by [importance sampling][is],
+/// where named inputs are drawn from proposal distributions instead of
+/// their own distribution.
+///
+/// Every sample is weighted by its likelihood ratio, which is computed from
+/// the densities of the inputs and their proposals. A good proposal puts more
+/// weight on the values which matter most for the result, for example the
+/// tail of an input which leads to a rare failure. This reduces the number
+/// of samples needed to reach the desired precision.
+///
+/// Inputs are the values labelled using [`named`](Uncertain::named). Inputs
+/// with a proposal must be sources with a known [`Density`], like
+/// [`Measurement`](crate::Measurement) or a [`Source`](crate::Source). Inputs with the same name are drawn from the same
+/// proposal, but are otherwise independent, unless they are shared using
+/// [`common_inputs`](Uncertain::common_inputs).
+///
+/// [is]: https://en.wikipedia.org/wiki/Importance_sampling
+///
+/// # Examples
+///
+/// Basic usage: the probability of an event in the tail of an input.
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, Importance, Options};
+///
+/// let load = Measurement::normal(0.0, 1.0).unwrap().named("load");
+/// let failure = load.map(|load| load > 4.0);
+///
+/// let importance = Importance::new().proposal("load", Measurement::normal(4.0, 1.0).unwrap());
+/// let report = importance.pr(&failure, 2e-6, &Options::new()).unwrap();
+/// assert!((report.value() - 3.167e-5).abs() < 2e-6);
+/// ```
+#[derive(Clone, Default)]
+pub struct Importance {
+    proposals: Vec<(String, Arc<dyn Density + Send + Sync>)>,
+}
+
+impl fmt::Debug for Importance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self
+            .proposals
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        f.debug_struct("Importance")
+            .field("proposals", &names)
+            .finish()
+    }
+}
+
+impl Importance {
+    /// Sample all inputs from their own distribution.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw the input labelled `name` from the distribution of `proposal`.
+    ///
+    /// The proposal should be positive wherever the distribution of the
+    /// input is, otherwise the results are biased.
+    pub fn proposal<D>(mut self, name: &str, proposal: D) -> Self
+    where
+        D: Density + Send + Sync + 'static,
+    {
+        let proposal = Arc::new(proposal);
+        match self.proposals.iter_mut().find(|(other, _)| other == name) {
+            Some((_, existing)) => *existing = proposal,
+            None => self.proposals.push((name.to_string(), proposal)),
+        }
+        self
+    }
+
+    fn compute<U, M, F>(
+        &self,
+        uncertain: &U,
+        to_float: M,
+        precision: F,
+        options: &Options,
+    ) -> Result<Report<F>, Error<F>>
+    where
+        U: Uncertain + ?Sized,
+        M: Fn(U::Value) -> F,
+        F: Float,
+    {
+        if precision <= F::zero() {
+            return Err(Error::InvalidPrecision(precision));
+        }
+        let weighted = Weighted {
+            uncertain,
+            to_float,
+            proposals: self.proposals.clone().into(),
+            unknown: RefCell::new(None),
+        };
+        let result = expectation::compute(&weighted, precision, options);
+        match weighted.unknown.into_inner() {
+            Some(input) => Err(Error::UnknownDensity { input }),
+            None => result,
+        }
+    }
+
+    /// Compute the expectation of `value` to the desired precision, see
+    /// [`expect_with`](Uncertain::expect_with).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownDensity`] if an input with a proposal does not
+    /// have a known distribution. Otherwise, see [`expect_with`](Uncertain::expect_with).
+    pub fn expect<U>(
+        &self,
+        value: &U,
+        precision: U::Value,
+        options: &Options,
+    ) -> Result<Report<U::Value>, Error<U::Value>>
+    where
+        U: Uncertain + ?Sized,
+        U::Value: Float,
+    {
+        self.compute(value, |value| value, precision, options)
+    }
+
+    /// Compute the probability that `event` is true to the desired
+    /// precision, as the expectation of its indicator.
+    ///
+    /// # Errors
+    ///
+    /// See [`expect`](Self::expect).
+    pub fn pr<U>(&self, event: &U, precision: f64, options: &Options) -> Result<Report<f64>, Error>
+    where
+        U: Uncertain<Value = bool> + ?Sized,
+    {
+        let indicator = |event: bool| if event { 1.0 } else { 0.0 };
+        self.compute(event, indicator, precision, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math, Exponential, Measurement, Source};
+
+    #[test]
+    fn proposals_estimate_tail_probability() {
+        let x = Measurement::normal(0.0, 1.0).unwrap().named("x");
+        let y = Measurement::uniform(0.0, 1.0).unwrap().named("y");
+        let event = x.add(y).map(|sum| sum > 5.0);
+
+        // P(x + y > 5) = int_0^1 P(x > 5 - y) dy
+        let exact: f64 = (0..1000)
+            .map(|i| 1.0 - math::normal_cdf(5.0 - (i as f64 + 0.5) / 1000.0))
+            .sum::<f64>()
+            / 1000.0;
+        let importance = Importance::new().proposal("x", Measurement::normal(4.5, 1.0).unwrap());
+        let report = importance
+            .pr(&event, exact / 10.0, &Options::new())
+            .unwrap();
+        assert!((report.value() - exact).abs() < exact / 10.0);
+        assert!(report.samples() < 10_000);
+    }
+
+    #[test]
+    fn inputs_need_known_density() {
+        let x = Measurement::normal(0.0, 1.0)
+            .unwrap()
+            .map(|x| x * 2.0)
+            .named("x");
+        let importance = Importance::new().proposal("x", Measurement::normal(1.0, 1.0).unwrap());
+        assert!(matches!(
+            importance.expect(&x, 0.1, &Options::new()),
+            Err(Error::UnknownDensity { input }) if input == "x"
+        ));
+
+        let wait = Source::from(Exponential::new(1.0).unwrap()).named("wait");
+        let importance = importance.proposal("wait", Exponential::new(0.1).unwrap());
+        let late = wait.map(|wait| wait > 10.0);
+        let report = importance.pr(&late, 5e-6, &Options::new()).unwrap();
+        assert!((report.value() - (-10f64).exp()).abs() < 5e-6);
+
+        let untouched = Measurement::normal(2.0, 1.0).unwrap().named("y");
+        let report = importance.expect(&untouched, 0.1, &Options::new()).unwrap();
+        assert!((report.value() - 2.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn shared_inputs_are_weighted_once() {
+        let input = || Measurement::normal(0.0, 1.0).unwrap().named("x");
+        let event = input().add(input()).map(|sum| sum > 8.0).common_inputs();
+
+        let exact = 1.0 - math::normal_cdf(4.0);
+        let importance = Importance::new().proposal("x", Measurement::normal(4.0, 1.0).unwrap());
+        let report = importance
+            .pr(&event, exact / 10.0, &Options::new())
+            .unwrap();
+        assert!((report.value() - exact).abs() < exact / 10.0);
+    }
+}
@@ -0,0 +1,57 @@
+//! Introspection of the uncertain values provided by this crate.
+
+use crate::law::Law;
+use crate::{graph, Density, Graph, Uncertain};
+use std::sync::Arc;
+
+/// Properties of an uncertain value which adapters and queries can make
+/// use of, see [`Uncertain::inspect`].
+///
+/// The trait is public, but lives in a private module, such that it can
+/// not be named or implemented outside of this crate.
+pub trait Inspect {
+    /// The distribution of this uncertain value, if it is known in closed
+    /// form. This allows adapters to use exact computations instead of
+    /// sampling where possible.
+    fn law(&self) -> Option<Law> {
+        None
+    }
+
+    /// The density of this uncertain value, if it is known in closed form.
+    /// This allows values to be drawn from a different distribution, whose
+    /// samples are weighted by their likelihood ratio.
+    fn density(&self) -> Option<Arc<dyn Density + Send + Sync>> {
+        let law = self.law()?;
+        Some(Arc::new(law))
+    }
+
+    /// Add the computation graph of this uncertain value to `graph`,
+    /// returning the index of the node representing this value.
+    fn visit(&self, graph: &mut Graph) -> usize;
+}
+
+/// The distribution of `uncertain`, if it is known in closed form.
+pub(crate) fn law<U: Uncertain + ?Sized>(uncertain: &U) -> Option<Law> {
+    uncertain.inspect()?.law()
+}
+
+/// The density of `uncertain`, if it is known in closed form.
+pub(crate) fn density<U: Uncertain + ?Sized>(
+    uncertain: &U,
+) -> Option<Arc<dyn Density + Send + Sync>> {
+    uncertain.inspect()?.density()
+}
+
+/// Add the computation graph of `uncertain` to `graph`. Values which can not
+/// be inspected are added as a single node labelled with the name of their type.
+pub(crate) fn visit<U: Uncertain + ?Sized>(uncertain: &U, graph: &mut Graph) -> usize {
+    match uncertain.inspect() {
+        Some(inspect) => inspect.visit(graph),
+        None => leaf(std::any::type_name::<U>(), graph),
+    }
+}
+
+/// Add a single node labelled with the type name `name` to `graph`.
+pub(crate) fn leaf(name: &str, graph: &mut Graph) -> usize {
+    graph.add(graph::short_type_name(name), Vec::new())
+}
@@ -0,0 +1,364 @@
+use crate::math;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// The shape of a distribution known in closed form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Normal { mean: f64, std_dev: f64 },
+    Uniform { low: f64, high: f64 },
+    Triangular { low: f64, mode: f64, high: f64 },
+}
+
+impl Shape {
+    fn support(&self) -> (f64, f64) {
+        match *self {
+            Shape::Normal { mean, std_dev: 0.0 } => (mean, mean),
+            Shape::Normal { .. } => (f64::NEG_INFINITY, f64::INFINITY),
+            Shape::Uniform { low, high } => (low, high),
+            Shape::Triangular { low, high, .. } => (low, high),
+        }
+    }
+
+    fn cdf(&self, x: f64) -> f64 {
+        match *self {
+            Shape::Normal { mean, std_dev } => {
+                if std_dev > 0.0 {
+                    math::normal_cdf((x - mean) / std_dev)
+                } else if x >= mean {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Shape::Uniform { low, high } => {
+                if x >= high {
+                    1.0
+                } else if x <= low {
+                    0.0
+                } else {
+                    (x - low) / (high - low)
+                }
+            }
+            Shape::Triangular { low, mode, high } => {
+                if x >= high {
+                    1.0
+                } else if x <= low {
+                    0.0
+                } else if x <= mode {
+                    (x - low) * (x - low) / ((high - low) * (mode - low))
+                } else {
+                    1.0 - (high - x) * (high - x) / ((high - low) * (high - mode))
+                }
+            }
+        }
+    }
+
+    fn log_pdf(&self, x: f64) -> f64 {
+        let (low, high) = self.support();
+        if x < low || x > high {
+            return f64::NEG_INFINITY;
+        } else if low == high {
+            return f64::INFINITY;
+        }
+        match *self {
+            Shape::Normal { mean, std_dev } => {
+                let z = (x - mean) / std_dev;
+                -0.5 * z * z - std_dev.ln() - 0.5 * (2.0 * PI).ln()
+            }
+            Shape::Uniform { low, high } => -(high - low).ln(),
+            Shape::Triangular { low, mode, high } => {
+                let pdf = if x < mode {
+                    2.0 * (x - low) / ((high - low) * (mode - low))
+                } else if x > mode {
+                    2.0 * (high - x) / ((high - low) * (high - mode))
+                } else {
+                    2.0 / (high - low)
+                };
+                pdf.ln()
+            }
+        }
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        match *self {
+            Shape::Normal { mean, std_dev } => mean + std_dev * math::normal_inverse_cdf(p),
+            Shape::Uniform { low, high } => low + p * (high - low),
+            Shape::Triangular { low, mode, high } => {
+                let split = if high > low {
+                    (mode - low) / (high - low)
+                } else {
+                    0.0
+                };
+                if p < split {
+                    low + (p * (high - low) * (mode - low)).sqrt()
+                } else {
+                    high - ((1.0 - p) * (high - low) * (high - mode)).sqrt()
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Normal { mean, std_dev } => write!(f, "normal({}, {})", mean, std_dev),
+            Shape::Uniform { low, high } => write!(f, "uniform({}, {})", low, high),
+            Shape::Triangular { low, mode, high } => {
+                write!(f, "triangular({}, {}, {})", low, mode, high)
+            }
+        }
+    }
+}
+
+/// A distribution which is known in closed form, optionally
+/// truncated to the range `[low, high]`.
+///
+/// This is used by uncertain values which model well known distributions,
+/// such that adapters can make use of their properties instead of sampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Law {
+    shape: Shape,
+    low: f64,
+    high: f64,
+}
+
+impl fmt::Display for Law {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.shape)?;
+        if self.low > f64::NEG_INFINITY || self.high < f64::INFINITY {
+            write!(f, " in [{}, {}]", self.low, self.high)?;
+        }
+        Ok(())
+    }
+}
+
+impl Law {
+    pub(crate) fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            low: f64::NEG_INFINITY,
+            high: f64::INFINITY,
+        }
+    }
+
+    /// The law conditioned on the value lying within `[low, high]`.
+    pub(crate) fn truncate(&self, low: f64, high: f64) -> Self {
+        Self {
+            shape: self.shape,
+            low: low.max(self.low),
+            high: high.min(self.high),
+        }
+    }
+
+    /// The probability mass the (truncated) distribution assigns to `[low, high]`.
+    pub(crate) fn mass(&self, low: f64, high: f64) -> f64 {
+        let (low, high) = (low.max(self.low), high.min(self.high));
+        if low > high {
+            return 0.0;
+        }
+        (self.shape.cdf(high) - self.shape.cdf(low)) / self.total()
+    }
+
+    fn total(&self) -> f64 {
+        self.shape.cdf(self.high) - self.shape.cdf(self.low)
+    }
+
+    /// The smallest range containing all values of the (truncated) distribution.
+    pub(crate) fn bounds(&self) -> (f64, f64) {
+        let (low, high) = self.shape.support();
+        (self.low.max(low), self.high.min(high))
+    }
+
+    /// The mean and variance of the (truncated) distribution.
+    pub(crate) fn moments(&self) -> (f64, f64) {
+        let (low, high) = self.bounds();
+        if low >= high {
+            return (low, 0.0);
+        }
+
+        match self.shape {
+            Shape::Normal { mean, std_dev } => {
+                // see https://en.wikipedia.org/wiki/Truncated_normal_distribution
+                let alpha = (low - mean) / std_dev;
+                let beta = (high - mean) / std_dev;
+                let mass = self.total();
+                let (pdf_alpha, pdf_beta) = (math::normal_pdf(alpha), math::normal_pdf(beta));
+                let shift = (pdf_alpha - pdf_beta) / mass;
+                let alpha_term = if alpha.is_finite() {
+                    alpha * pdf_alpha
+                } else {
+                    0.0
+                };
+                let beta_term = if beta.is_finite() {
+                    beta * pdf_beta
+                } else {
+                    0.0
+                };
+                let scale = 1.0 + (alpha_term - beta_term) / mass - shift * shift;
+                (mean + std_dev * shift, std_dev * std_dev * scale.max(0.0))
+            }
+            Shape::Uniform { .. } => ((low + high) / 2.0, (high - low).powi(2) / 12.0),
+            Shape::Triangular {
+                low: a,
+                mode: c,
+                high: b,
+            } => {
+                // The density is linear on both sides of the mode, such that
+                // Simpson's rule is exact for the first three moments.
+                let pdf = |x: f64| {
+                    if x <= c && c > a {
+                        2.0 * (x - a) / ((b - a) * (c - a))
+                    } else if x >= c && b > c {
+                        2.0 * (b - x) / ((b - a) * (b - c))
+                    } else {
+                        2.0 / (b - a)
+                    }
+                };
+                let simpson = |from: f64, to: f64, power: i32| {
+                    if from >= to {
+                        return 0.0;
+                    }
+                    let mid = (from + to) / 2.0;
+                    let f = |x: f64| x.powi(power) * pdf(x);
+                    (to - from) / 6.0 * (f(from) + 4.0 * f(mid) + f(to))
+                };
+                let moment =
+                    |power| simpson(low, high.min(c), power) + simpson(low.max(c), high, power);
+                let mass = moment(0);
+                let mean = moment(1) / mass;
+                (mean, (moment(2) / mass - mean * mean).max(0.0))
+            }
+        }
+    }
+
+    /// The parameters of the distribution if it is an untruncated
+    /// normal distribution.
+    pub(crate) fn normal(&self) -> Option<(f64, f64)> {
+        match self.shape {
+            Shape::Normal { mean, std_dev }
+                if self.low == f64::NEG_INFINITY && self.high == f64::INFINITY =>
+            {
+                Some((mean, std_dev))
+            }
+            _ => None,
+        }
+    }
+
+    /// The cumulative distribution function of the (truncated) distribution.
+    pub(crate) fn cdf(&self, x: f64) -> f64 {
+        if x < self.low {
+            return 0.0;
+        } else if x >= self.high {
+            return 1.0;
+        }
+        ((self.shape.cdf(x) - self.shape.cdf(self.low)) / self.total()).clamp(0.0, 1.0)
+    }
+
+    /// The logarithm of the probability density of the (truncated) distribution.
+    pub(crate) fn log_pdf(&self, x: f64) -> f64 {
+        if x < self.low || x > self.high {
+            return f64::NEG_INFINITY;
+        }
+        self.shape.log_pdf(x) - self.total().ln()
+    }
+
+    pub(crate) fn inverse_cdf(&self, p: f64) -> f64 {
+        let start = self.shape.cdf(self.low);
+        let x = self.shape.inverse_cdf(start + p * self.total());
+        x.max(self.low).min(self.high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_inverse_cdf_stays_in_range() {
+        let law = Law::new(Shape::Normal {
+            mean: 0.0,
+            std_dev: 1.0,
+        })
+        .truncate(0.5, 2.0);
+        for i in 1..100 {
+            let x = law.inverse_cdf(i as f64 / 100.0);
+            assert!((0.5..=2.0).contains(&x));
+        }
+        assert!((law.mass(0.5, 2.0) - 1.0).abs() < 1e-12);
+        assert_eq!(law.mass(-1.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn triangular_cdf_inverts() {
+        let shape = Shape::Triangular {
+            low: -1.0,
+            mode: 0.5,
+            high: 2.0,
+        };
+        for i in 1..100 {
+            let p = i as f64 / 100.0;
+            assert!((shape.cdf(shape.inverse_cdf(p)) - p).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn moments_are_correct() {
+        let law = Law::new(Shape::Uniform {
+            low: 1.0,
+            high: 3.0,
+        });
+        assert_eq!(law.moments(), (2.0, 1.0 / 3.0));
+        assert_eq!(law.truncate(2.0, 5.0).moments(), (2.5, 1.0 / 12.0));
+
+        let law = Law::new(Shape::Triangular {
+            low: 0.0,
+            mode: 1.0,
+            high: 5.0,
+        });
+        let (mean, var) = law.moments();
+        assert!((mean - 2.0).abs() < 1e-12);
+        assert!((var - 21.0 / 18.0).abs() < 1e-12);
+
+        let law = Law::new(Shape::Normal {
+            mean: 1.0,
+            std_dev: 2.0,
+        });
+        assert_eq!(law.moments(), (1.0, 4.0));
+        let (mean, var) = law.truncate(1.0, f64::INFINITY).moments();
+        let half_normal_mean = 2.0 * (2.0 / std::f64::consts::PI).sqrt();
+        assert!((mean - 1.0 - half_normal_mean).abs() < 1e-12);
+        assert!((var - 4.0 * (1.0 - 2.0 / std::f64::consts::PI)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn densities_are_normalized() {
+        let laws = [
+            Law::new(Shape::Normal {
+                mean: 1.0,
+                std_dev: 2.0,
+            })
+            .truncate(0.0, 3.0),
+            Law::new(Shape::Uniform {
+                low: -1.0,
+                high: 1.0,
+            }),
+            Law::new(Shape::Triangular {
+                low: 0.0,
+                mode: 1.0,
+                high: 4.0,
+            }),
+        ];
+        for law in laws.iter() {
+            let (low, high) = law.bounds();
+            let steps = 10_000;
+            let width = (high - low) / steps as f64;
+            let mass: f64 = (0..steps)
+                .map(|i| law.log_pdf(low + (i as f64 + 0.5) * width).exp() * width)
+                .sum();
+            assert!((mass - 1.0).abs() < 1e-6, "{}", law);
+            assert_eq!(law.log_pdf(high + 1.0), f64::NEG_INFINITY);
+        }
+    }
+}
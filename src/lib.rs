@@ -31,6 +31,11 @@
 //! is by design: using Wald's [sequential probability ratio test][sprt], evaluation typically
 //! takes less than `100` samples.
 //!
+//! # Features
+//!
+//! - `serde`: implement `Serialize` and `Deserialize` for source specifications ([`Spec`])
+//!   and the results of queries, like [`Report`] and [`ConvergenceError`].
+//!
 //! # References
 //!
 //! The [`Uncertain`] trait exported from the library is an implementation of
@@ -40,23 +45,66 @@
 //! [sprt]: https://en.wikipedia.org/wiki/Sequential_probability_ratio_test
 
 use adapters::*;
+use inspect::Inspect;
 use num_traits::{identities, Float};
 use rand_pcg::Pcg32;
 use reference::RefUncertain;
+use shared::Shared;
 
 mod adapters;
 mod boxed;
+mod common;
+mod context;
+mod decision;
+mod density;
+mod design;
 mod dist;
+mod empirical;
+mod error;
 mod expectation;
+mod graph;
+mod importance;
+mod inspect;
+mod law;
+mod math;
+mod measurement;
+mod model;
+mod multivariate;
+mod options;
 mod point;
+mod rare;
 mod reference;
+mod risk;
+mod sensitivity;
+mod shared;
+mod spec;
 mod sprt;
+mod symbolic;
 
 pub use boxed::BoxedUncertain;
+pub use decision::{Choice, Decision};
+pub use density::{Beta, Binomial, Density, Exponential, Gamma, Normal, Poisson, Source, Uniform};
 pub use dist::Distribution;
+pub use empirical::{Bootstrap, Empirical};
+pub use error::{Error, Interrupted};
+pub use graph::{Graph, Node};
+pub use importance::Importance;
+pub use measurement::{Measurement, ParameterError, Tolerance};
+pub use model::{Model, ParseError, Query};
+pub use multivariate::{GaussianCopula, MultivariateNormal};
+pub use options::{CancellationToken, Limit, NonFinite, Options, Sampling};
 pub use point::PointMass;
+pub use risk::RiskReport;
+pub use sensitivity::{SensitivityError, SobolIndices};
+pub use spec::Spec;
+pub use sprt::TestReport;
+pub use symbolic::{Estimate, Symbolic, UnsupportedError};
 
-pub use expectation::ConvergenceError;
+pub use adapters::TruncationError;
+pub use expectation::{
+    compare, compare_with, correlation, correlation_with, covariance, covariance_with,
+    expect_with_control, ConvergenceError, Report,
+};
 
 pub(crate) type Rng = Pcg32;
 
@@ -101,6 +149,16 @@ pub trait Uncertain {
     /// [`Into<Distribution>`]: Distribution
     fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value;
 
+    /// Properties of this uncertain value which adapters and queries can make
+    /// use of, like its distribution if it is known in closed form.
+    ///
+    /// This is used by the uncertain values provided by this crate. It can
+    /// not be implemented outside of it.
+    #[doc(hidden)]
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        None
+    }
+
     /// Determine if the probability of obtaining `true` form this uncertain
     /// value is at least `probability`.
     ///
@@ -137,14 +195,85 @@ pub trait Uncertain {
     /// assert_eq!(y.pr(0.5), false);
     /// ```
     fn pr(&self, probability: f32) -> bool
+    where
+        Self::Value: Into<bool>,
+    {
+        match self.try_pr(probability) {
+            Ok(result) => result,
+            Err(_) => panic!("Probability {:?} must be in (0, 1)", probability),
+        }
+    }
+
+    /// Determine if the probability of obtaining `true` form this uncertain
+    /// value is at least `probability`. This is the same as [`pr`](Self::pr),
+    /// but returns an error instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidProbability`] if `probability <= 0 || probability >= 1`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution, Error};
+    /// use rand_distr::Bernoulli;
+    ///
+    /// let x = Distribution::from(Bernoulli::new(0.8).unwrap());
+    /// assert_eq!(x.try_pr(0.5).unwrap(), true);
+    /// assert!(matches!(x.try_pr(1.5), Err(Error::InvalidProbability(_))));
+    /// ```
+    fn try_pr(&self, probability: f32) -> Result<bool, Error>
+    where
+        Self::Value: Into<bool>,
+    {
+        self.pr_with(probability, &Options::default())
+            .map(|report| report.result())
+    }
+
+    /// Determine if the probability of obtaining `true` form this uncertain
+    /// value is at least `probability`, using the given options. This is the
+    /// same as [`try_pr`](Self::try_pr), but allows limiting the resources
+    /// used by the test, and reports how many samples it took.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidProbability`] if `probability <= 0 || probability >= 1`
+    /// and [`Error::Interrupted`] if the test exceeds its budget before reaching a
+    /// conclusion.
+    ///
+    /// # Examples
+    ///
+    /// Limiting the number of samples:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution, Error, Options};
+    /// use rand_distr::Bernoulli;
+    ///
+    /// let x = Distribution::from(Bernoulli::new(0.5).unwrap());
+    /// let report = x.pr_with(0.2, &Options::new()).unwrap();
+    /// assert!(report.result());
+    /// assert!(report.samples() > 0);
+    ///
+    /// let options = Options::new().max_samples(5);
+    /// match x.pr_with(0.5, &options) {
+    ///     Err(Error::Interrupted(partial)) => {
+    ///         assert_eq!(partial.samples(), 5);
+    ///         assert!(partial.estimate() >= 0.0 && partial.estimate() <= 1.0);
+    ///     }
+    ///     _ => panic!("test should not be conclusive"),
+    /// }
+    /// ```
+    fn pr_with(&self, probability: f32, options: &Options) -> Result<TestReport, Error>
     where
         Self::Value: Into<bool>,
     {
         if probability <= 0.0 || probability >= 1.0 {
-            panic!("Probability {:?} must be in (0, 1)", probability);
+            return Err(Error::InvalidProbability(probability));
         }
 
-        sprt::compute(self, probability)
+        sprt::compute(self, probability, options)
     }
 
     /// Calculate the expectation of this uncertain value to the desired
@@ -204,14 +333,373 @@ pub trait Uncertain {
     /// [multi-modal]: https://en.wikipedia.org/wiki/Multimodal_distribution
     /// [two-sigma]: https://en.wikipedia.org/wiki/68–95–99.7_rule
     fn expect(&self, precision: Self::Value) -> Result<Self::Value, ConvergenceError<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        expectation::unwrap_precision(self.expect_with(precision, &Options::default()), precision)
+    }
+
+    /// Calculate the expectation of this uncertain value to the desired
+    /// precision. This is the same as [`expect`](Self::expect), but returns
+    /// an error instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPrecision`] if `precision <= 0`,
+    /// [`Error::NonFinite`] if a sample is `NaN` or infinite and [`Error::NonConvergence`]
+    /// if the expected value does not converge to within the desired precision.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution, Error};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(5.0, 1.0).unwrap());
+    /// let mu: f64 = x.try_expect(0.1).unwrap();
+    /// assert!((mu - 5.0).abs() < 0.1);
+    /// assert!(matches!(x.try_expect(-1.0), Err(Error::InvalidPrecision(_))));
+    ///
+    /// let y = x.map(|x| if x < 5.0 { f64::NAN } else { x });
+    /// assert!(matches!(y.try_expect(0.1), Err(Error::NonFinite { .. })));
+    /// ```
+    fn try_expect(&self, precision: Self::Value) -> Result<Self::Value, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        self.expect_with(precision, &Options::default())
+            .map(|report| report.value())
+    }
+
+    /// Calculate the expectation of this uncertain value to the desired
+    /// precision, using the given options. This is the same as
+    /// [`try_expect`](Self::try_expect), but returns a [`Report`] which
+    /// includes details about the sampling process.
+    ///
+    /// # Errors
+    ///
+    /// See [`try_expect`](Self::try_expect).
+    ///
+    /// # Examples
+    ///
+    /// Skipping samples which are not finite:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution, NonFinite, Options};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(0.0, 1.0).unwrap());
+    /// let y = x.map(|x: f64| 1.0 / x.max(0.0));
+    ///
+    /// let options = Options::new().non_finite(NonFinite::Skip);
+    /// let report = y.expect_with(10.0, &options).unwrap();
+    /// assert!(report.value() > 0.0);
+    /// assert!(report.non_finite_samples() > 0);
+    /// ```
+    fn expect_with(
+        &self,
+        precision: Self::Value,
+        options: &Options,
+    ) -> Result<Report<Self::Value>, Error<Self::Value>>
     where
         Self::Value: Float,
     {
         if precision <= identities::zero() {
-            panic!("Precision must be larger than 0");
+            return Err(Error::InvalidPrecision(precision));
         }
 
-        expectation::compute(self, precision)
+        expectation::compute(self, precision, options)
+    }
+
+    /// Calculate the quantile `q` of this uncertain value to the desired
+    /// precision, i.e. the value which is larger than a fraction `q` of
+    /// all samples. For example, `q = 0.5` yields the median.
+    ///
+    /// The estimate converges if the [two sigma confidence interval][two-sigma]
+    /// of the quantile, derived from the [order statistics][order] of the samples,
+    /// is smaller than the desired precision. See [`expect`](Self::expect).
+    ///
+    /// [two-sigma]: https://en.wikipedia.org/wiki/68–95–99.7_rule
+    /// [order]: https://en.wikipedia.org/wiki/Order_statistic
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `q` is not in `(0, 1)`, if `precision <= 0` or if
+    /// the estimate did not converge. See [`try_expect`](Self::try_expect).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement};
+    ///
+    /// let x = Measurement::normal(10.0, 2.0).unwrap();
+    /// let median = x.quantile(0.5, 0.2).unwrap();
+    /// assert!((median - 10.0).abs() < 0.2);
+    /// ```
+    fn quantile(&self, q: f64, precision: Self::Value) -> Result<Self::Value, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        self.quantile_with(q, precision, &Options::default())
+            .map(|report| report.value())
+    }
+
+    /// Calculate the quantile `q` of this uncertain value to the desired
+    /// precision, using the given options. This is the same as
+    /// [`quantile`](Self::quantile), but returns a [`Report`] which
+    /// includes details about the sampling process.
+    ///
+    /// # Errors
+    ///
+    /// See [`quantile`](Self::quantile).
+    fn quantile_with(
+        &self,
+        q: f64,
+        precision: Self::Value,
+        options: &Options,
+    ) -> Result<Report<Self::Value>, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        if q.is_nan() || q <= 0.0 || q >= 1.0 {
+            return Err(Error::InvalidProbability(q as f32));
+        }
+        if precision <= identities::zero() {
+            return Err(Error::InvalidPrecision(precision));
+        }
+
+        expectation::quantile(self, q, precision, options)
+    }
+
+    /// Calculate the [value at risk][var] of this uncertain value at the
+    /// level `alpha` to the desired precision, where larger values are worse
+    /// outcomes, like losses or latencies. This is the quantile `alpha`, which
+    /// is exceeded with a probability of `1 - alpha`, see [`quantile`](Self::quantile).
+    ///
+    /// [var]: https://en.wikipedia.org/wiki/Value_at_risk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `alpha` is not in `(0, 1)`, if `precision <= 0` or
+    /// if the estimate did not converge. See [`try_expect`](Self::try_expect).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement};
+    ///
+    /// let loss = Measurement::normal(100.0, 10.0).unwrap();
+    /// let var = loss.value_at_risk(0.95, 1.0).unwrap();
+    /// assert!((var - 116.45).abs() < 1.0);
+    /// ```
+    fn value_at_risk(
+        &self,
+        alpha: f64,
+        precision: Self::Value,
+    ) -> Result<Self::Value, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        self.value_at_risk_with(alpha, precision, &Options::default())
+            .map(|report| report.value())
+    }
+
+    /// Calculate the value at risk of this uncertain value to the desired
+    /// precision, using the given options. This is the same as
+    /// [`value_at_risk`](Self::value_at_risk), but returns a [`RiskReport`]
+    /// which includes the number of samples in the tail. The estimate is that
+    /// of [`quantile_with`](Self::quantile_with).
+    ///
+    /// # Errors
+    ///
+    /// See [`value_at_risk`](Self::value_at_risk).
+    fn value_at_risk_with(
+        &self,
+        alpha: f64,
+        precision: Self::Value,
+        options: &Options,
+    ) -> Result<RiskReport<Self::Value>, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        let report = self.quantile_with(alpha, precision, options)?;
+        Ok(RiskReport::value_at_risk(report, alpha))
+    }
+
+    /// Calculate the [expected shortfall][es] of this uncertain value at the
+    /// level `alpha` to the desired precision, where larger values are worse
+    /// outcomes. This is the expectation of the value, given that it is at
+    /// least the [value at risk](Self::value_at_risk).
+    ///
+    /// The estimate is the mean of the samples in the tail. Its standard error
+    /// accounts for the uncertainty of the value at risk, and is considered
+    /// unknown until the tail contains at least ten samples. Otherwise, the
+    /// convergence criterion is the same as for [`expect`](Self::expect).
+    ///
+    /// [es]: https://en.wikipedia.org/wiki/Expected_shortfall
+    ///
+    /// # Errors
+    ///
+    /// See [`value_at_risk`](Self::value_at_risk).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage: the mean of the worst five percent of outcomes.
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement, Options};
+    ///
+    /// let latency = Measurement::normal(100.0, 10.0).unwrap();
+    /// let report = latency.expected_shortfall_with(0.95, 1.0, &Options::new()).unwrap();
+    /// assert!((report.value() - 120.63).abs() < 1.0);
+    /// assert!(report.tail_samples() > 10);
+    /// ```
+    fn expected_shortfall(
+        &self,
+        alpha: f64,
+        precision: Self::Value,
+    ) -> Result<Self::Value, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        self.expected_shortfall_with(alpha, precision, &Options::default())
+            .map(|report| report.value())
+    }
+
+    /// Calculate the expected shortfall of this uncertain value to the desired
+    /// precision, using the given options. This is the same as
+    /// [`expected_shortfall`](Self::expected_shortfall), but returns a
+    /// [`RiskReport`] which includes the number of samples in the tail.
+    ///
+    /// # Errors
+    ///
+    /// See [`value_at_risk`](Self::value_at_risk).
+    fn expected_shortfall_with(
+        &self,
+        alpha: f64,
+        precision: Self::Value,
+        options: &Options,
+    ) -> Result<RiskReport<Self::Value>, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        if alpha.is_nan() || alpha <= 0.0 || alpha >= 1.0 {
+            return Err(Error::InvalidProbability(alpha as f32));
+        }
+        if precision <= identities::zero() {
+            return Err(Error::InvalidPrecision(precision));
+        }
+
+        risk::expected_shortfall(self, alpha, precision, options)
+    }
+
+    /// Determine which inputs cause the uncertainty of this value, using
+    /// [variance based sensitivity analysis][sobol].
+    ///
+    /// Inputs are the values labelled using [`named`](Self::named). Their
+    /// first order and total Sobol indices are estimated using the scheme by
+    /// Saltelli, which evaluates `self` a total of `samples * (inputs + 2)`
+    /// times. During every evaluation, each input is either resampled or
+    /// frozen to a value from a previous evaluation, independent of all
+    /// other inputs.
+    ///
+    /// For the indices to be meaningful, all sources of uncertainty should be
+    /// contained within a named input and inputs should be independent.
+    /// Inputs are identified by their name, such that inputs with the same
+    /// name always yield the same value. A value shared using
+    /// [`into_ref`](Self::into_ref) or [`into_shared`](Self::into_shared)
+    /// should be named itself, rather than be used inside of an input and by
+    /// other parts of the model: every input is frozen or resampled on its
+    /// own, such that the parts would no longer agree on the shared value.
+    ///
+    /// [sobol]: https://en.wikipedia.org/wiki/Variance-based_sensitivity_analysis
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `samples` is `0`, or if the value is constant or
+    /// not finite, such that its variance can not be attributed to the inputs.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage: the area of a rectangle with a badly known width.
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement};
+    ///
+    /// let width = Measurement::normal(2.0, 0.5).unwrap().named("width");
+    /// let height = Measurement::normal(3.0, 0.1).unwrap().named("height");
+    /// let area = width.mul(height);
+    ///
+    /// let indices = area.sobol_indices(1000).unwrap();
+    /// assert!(indices.first_order("width").unwrap() > 0.9);
+    /// assert!(indices.total("height").unwrap() < 0.1);
+    /// ```
+    fn sobol_indices(&self, samples: usize) -> Result<SobolIndices, SensitivityError>
+    where
+        Self::Value: Into<f64>,
+    {
+        sensitivity::compute(self, samples)
+    }
+
+    /// Estimate the probability that this value is at least `threshold`,
+    /// when this probability is too small to be tested using [`pr`](Self::pr).
+    ///
+    /// The probability is estimated using [subset simulation][subset], which
+    /// reaches the rare event through a sequence of intermediate thresholds,
+    /// each exceeded by about a tenth of the samples above the previous one.
+    /// Samples above a threshold are found by perturbing the random numbers
+    /// drawn for samples above the previous threshold. This works best for
+    /// values computed from [`Measurement`]s, whose numbers can be perturbed
+    /// slightly; other sources are resampled independently. The number of
+    /// samples needed grows with the logarithm of the probability, rather
+    /// than its inverse, such that probabilities down to about `1e-10` can
+    /// be estimated using at most `10_000` samples.
+    ///
+    /// If none of the sources can be perturbed, like for a [`Distribution`],
+    /// or if the perturbed samples never exceed the next threshold, the
+    /// probability is instead estimated from `10_000` independent samples,
+    /// which can not resolve probabilities much below `1e-3`.
+    ///
+    /// The [`relative_error`](Report::relative_error) of the report shows how
+    /// reliable the estimate is. If no sample reaches the threshold, the
+    /// estimated probability is zero, and the two sigma error is the upper
+    /// bound of its `95%` confidence interval. Non-finite values are treated according
+    /// to the [options](Options::non_finite), where skipped values never
+    /// exceed the threshold. The [sampling design](Options::sampling) is ignored.
+    ///
+    /// [subset]: https://doi.org/10.1016/S0266-8920(01)00019-4
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is [interrupted](Error::Interrupted), or
+    /// if a value is not finite and the options demand an error.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage: the probability of a one in ten million failure.
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement, Options};
+    ///
+    /// let load = Measurement::normal(10.0, 1.0).unwrap();
+    /// let strength = Measurement::normal(15.8, 0.5).unwrap();
+    /// let margin = load.sub(strength);
+    ///
+    /// let report = margin.exceedance_pr(0.0, &Options::new()).unwrap();
+    /// assert!(report.value() > 1e-8 && report.value() < 1e-6);
+    /// assert!(report.relative_error() < 1.0);
+    /// ```
+    fn exceedance_pr(&self, threshold: f64, options: &Options) -> Result<Report<f64>, Error>
+    where
+        Self::Value: Into<f64>,
+    {
+        rare::compute(self, threshold, options)
     }
 
     /// Box this uncertain value, such that it's type becomes opaque. This is
@@ -275,6 +763,35 @@ pub trait Uncertain {
         RefUncertain::new(self)
     }
 
+    /// Bundle this uncertain value with a cache, so it can be reused by cloning it.
+    ///
+    /// This is an alternative to [`into_ref`](Self::into_ref) for when the graph
+    /// using the value needs to own it, for example to create a [`BoxedUncertain`].
+    /// All clones of the returned value share the same samples for every epoch.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, BoxedUncertain, Measurement};
+    ///
+    /// fn distance(x: Measurement) -> BoxedUncertain<f64> {
+    ///     let x = x.into_shared();
+    ///     x.clone().mul(x).sqrt().into_boxed()
+    /// }
+    ///
+    /// let d = distance(Measurement::normal(-3.0, 0.1).unwrap());
+    /// assert!(d.map(|d| d > 2.9).pr(0.9));
+    /// ```
+    fn into_shared(self) -> Shared<Self>
+    where
+        Self: Sized,
+        Self::Value: Clone,
+    {
+        Shared::new(self)
+    }
+
     /// Takes an uncertain value and produces another which
     /// generates values by calling a closure.
     ///
@@ -439,6 +956,102 @@ pub trait Uncertain {
         Or::new(self, other)
     }
 
+    /// Test if `self` is less than `other`. This is a shorthand
+    /// for `x.join(y, |x, y| x < y)`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(1.0, 1.0).unwrap());
+    /// let y = Distribution::from(Normal::new(4.0, 1.0).unwrap());
+    /// assert!(x.lt(y).pr(0.9));
+    /// ```
+    fn lt<U>(self, other: U) -> LessThan<Self, U>
+    where
+        Self: Sized,
+        U: Uncertain,
+        Self::Value: PartialOrd<U::Value>,
+    {
+        LessThan::new(self, other)
+    }
+
+    /// Test if `self` is less than or equal to `other`. This is a shorthand
+    /// for `x.join(y, |x, y| x <= y)`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(1.0, 1.0).unwrap());
+    /// let y = Distribution::from(Normal::new(4.0, 1.0).unwrap());
+    /// assert!(x.le(y).pr(0.9));
+    /// ```
+    fn le<U>(self, other: U) -> LessEqual<Self, U>
+    where
+        Self: Sized,
+        U: Uncertain,
+        Self::Value: PartialOrd<U::Value>,
+    {
+        LessEqual::new(self, other)
+    }
+
+    /// Test if `self` is greater than `other`. This is a shorthand
+    /// for `x.join(y, |x, y| x > y)`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(4.0, 1.0).unwrap());
+    /// let y = Distribution::from(Normal::new(1.0, 1.0).unwrap());
+    /// assert!(x.gt(y).pr(0.9));
+    /// ```
+    fn gt<U>(self, other: U) -> GreaterThan<Self, U>
+    where
+        Self: Sized,
+        U: Uncertain,
+        Self::Value: PartialOrd<U::Value>,
+    {
+        GreaterThan::new(self, other)
+    }
+
+    /// Test if `self` is greater than or equal to `other`. This is a shorthand
+    /// for `x.join(y, |x, y| x >= y)`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(4.0, 1.0).unwrap());
+    /// let y = Distribution::from(Normal::new(1.0, 1.0).unwrap());
+    /// assert!(x.ge(y).pr(0.9));
+    /// ```
+    fn ge<U>(self, other: U) -> GreaterEqual<Self, U>
+    where
+        Self: Sized,
+        U: Uncertain,
+        Self::Value: PartialOrd<U::Value>,
+    {
+        GreaterEqual::new(self, other)
+    }
+
     /// Add two uncertain values. This is a shorthand
     /// for `x.join(y, |x, y| x + y)`.
     ///
@@ -452,7 +1065,7 @@ pub trait Uncertain {
     ///
     /// let x = Distribution::from(Normal::new(1.0, 1.0).unwrap());
     /// let y = Distribution::from(Normal::new(4.0, 1.0).unwrap());
-    /// assert!(x.add(y).map(|sum| sum >= 5.0).pr(0.5));
+    /// assert!(x.add(y).map(|sum| sum >= 4.0).pr(0.5));
     /// ```
     fn add<U>(self, other: U) -> Sum<Self, U>
     where
@@ -534,4 +1147,268 @@ pub trait Uncertain {
     {
         Ratio::new(self, other)
     }
+
+    /// Take the square root of an uncertain value. This is a shorthand
+    /// for `x.map(|x| x.sqrt())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(9.0, 0.1).unwrap());
+    /// assert!(x.sqrt().map(|v| v > 2.9).pr(0.9));
+    /// ```
+    fn sqrt(self) -> Sqrt<Self>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Sqrt::new(self)
+    }
+
+    /// Exponentiate an uncertain value. This is a shorthand
+    /// for `x.map(|x| x.exp())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(0.0, 0.1).unwrap());
+    /// assert!(x.exp().map(|v| v > 0.0).pr(0.999));
+    /// ```
+    fn exp(self) -> Exp<Self>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Exp::new(self)
+    }
+
+    /// Take the natural logarithm of an uncertain value. This is a shorthand
+    /// for `x.map(|x| x.ln())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(10.0, 0.1).unwrap());
+    /// assert!(x.ln().map(|v| v > 2.0).pr(0.9));
+    /// ```
+    fn ln(self) -> Ln<Self>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Ln::new(self)
+    }
+
+    /// Take the sine of an uncertain value. This is a shorthand
+    /// for `x.map(|x| x.sin())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(0.0, 0.1).unwrap());
+    /// assert!(x.sin().map(|v: f64| v.abs() < 0.5).pr(0.9));
+    /// ```
+    fn sin(self) -> Sin<Self>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Sin::new(self)
+    }
+
+    /// Take the cosine of an uncertain value. This is a shorthand
+    /// for `x.map(|x| x.cos())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(0.0, 0.1).unwrap());
+    /// assert!(x.cos().map(|v| v > 0.9).pr(0.9));
+    /// ```
+    fn cos(self) -> Cos<Self>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Cos::new(self)
+    }
+
+    /// Take the absolute value of an uncertain value. This is a shorthand
+    /// for `x.map(|x| x.abs())`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(0.0, 1.0).unwrap());
+    /// assert!(x.abs().map(|v| v >= 0.0).pr(0.999));
+    /// ```
+    fn abs(self) -> Abs<Self>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Abs::new(self)
+    }
+
+    /// Describe the computation graph of this uncertain value, e.g. to
+    /// inspect or visualize a complex model.
+    ///
+    /// See [`Graph`] for details.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let x = Distribution::from(Normal::new(5.0, 2.0).unwrap());
+    /// let y = x.map(|x| x > 4.0);
+    /// let graph = y.describe();
+    /// assert_eq!(graph.nodes().len(), 2);
+    /// assert_eq!(graph.nodes()[1].label(), "map");
+    /// ```
+    fn describe(&self) -> Graph {
+        let mut graph = Graph::new();
+        inspect::visit(self, &mut graph);
+        graph
+    }
+
+    /// Label this uncertain value with a name. This does not change the
+    /// value, but the name is shown when [describing](Self::describe)
+    /// the computation graph.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let speed = Distribution::from(Normal::new(5.0, 2.0).unwrap()).named("speed");
+    /// let graph = speed.describe();
+    /// assert_eq!(graph.nodes()[1].label(), "speed");
+    /// ```
+    fn named(self, name: &str) -> Named<Self>
+    where
+        Self: Sized,
+    {
+        Named::new(self, name)
+    }
+
+    /// Share the random numbers drawn by inputs with the same
+    /// [name](Self::named). Within every sample of the returned value,
+    /// inputs named the same produce the same sample, even if they are
+    /// separate nodes, as long as they draw from the same distribution.
+    ///
+    /// This makes it possible to ask questions about two variants of a
+    /// model built from the same inputs, using [common random numbers][crn].
+    /// To compare their expectations, see [`compare`](crate::compare).
+    ///
+    /// [crn]: https://en.wikipedia.org/wiki/Variance_reduction#Common_Random_Numbers_(CRN)
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement};
+    ///
+    /// let latency = |overhead: f64| {
+    ///     let load = Measurement::normal(10.0, 3.0).unwrap().named("load");
+    ///     load.map(move |load| load + overhead)
+    /// };
+    ///
+    /// let faster = latency(0.1).gt(latency(0.0));
+    /// assert!(!faster.pr(0.6));
+    /// assert!(faster.common_inputs().pr(0.99));
+    /// ```
+    fn common_inputs(self) -> Common<Self>
+    where
+        Self: Sized,
+    {
+        Common::new(self)
+    }
+
+    /// Restrict an uncertain value to the range `[low, high]`. Samples outside
+    /// of the range are discarded, which is equivalent to conditioning the
+    /// value on lying within the range.
+    ///
+    /// This should be preferred over clamping the value using
+    /// [`map`](Self::map), which would move all the probability mass outside
+    /// of the range onto its bounds.
+    ///
+    /// Values are truncated by repeatedly sampling `self` until a sample lies
+    /// within the range. For distributions which are known in closed form,
+    /// like [`Measurement`], the truncated distribution is instead sampled
+    /// directly using its inverse cumulative distribution function.
+    ///
+    /// If no sample within the range is found after a large number of attempts,
+    /// the truncated value yields `NaN`. Since shared values yield the same
+    /// sample within an epoch (see [`sample`](Self::sample)), they can not be
+    /// resampled: if a shared value lies outside of the range, the truncated
+    /// value yields `NaN` immediately. Queries handle these samples as
+    /// configured by [`Options::non_finite`], which returns an error by default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `low > high` or if only very few samples
+    /// (less than `1%`) lie within the range.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage: a length can not be negative.
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Distribution};
+    /// use rand_distr::Normal;
+    ///
+    /// let length = Distribution::from(Normal::new(0.5, 1.0).unwrap())
+    ///     .truncate(0.0, f64::INFINITY)
+    ///     .unwrap();
+    /// assert!(length.map(|l| l >= 0.0).pr(0.999));
+    /// ```
+    fn truncate(
+        self,
+        low: Self::Value,
+        high: Self::Value,
+    ) -> Result<Truncated<Self>, TruncationError>
+    where
+        Self: Sized,
+        Self::Value: Float,
+    {
+        Truncated::new(self, low, high)
+    }
 }
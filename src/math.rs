@@ -0,0 +1,311 @@
+//! Special functions used by the built-in distributions.
+
+use std::f64::consts::{PI, SQRT_2};
+
+/// Probability density function of the standard normal distribution.
+pub fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
+
+/// Cumulative distribution function of the standard normal distribution.
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * libm::erfc(-x / SQRT_2)
+}
+
+/// Inverse of the cumulative distribution function of the
+/// standard normal distribution, using algorithm AS241 by Wichura (1988),
+/// which is accurate to about `1e-16`.
+pub fn normal_inverse_cdf(p: f64) -> f64 {
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    } else if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    let q = p - 0.5;
+    if q.abs() <= 0.425 {
+        let r = 0.180625 - q * q;
+        let num = ((((((2.509_080_928_730_122_7e3 * r + 3.343_057_558_358_813e4) * r
+            + 6.726_577_092_700_87e4)
+            * r
+            + 4.592_195_393_154_987e4)
+            * r
+            + 1.373_169_376_550_946e4)
+            * r
+            + 1.971_590_950_306_551_3e3)
+            * r
+            + 1.331_416_678_917_843_8e2)
+            * r
+            + 3.387_132_872_796_366_5;
+        let den = ((((((5.226_495_278_852_854e3 * r + 2.872_908_573_572_194_3e4) * r
+            + 3.930_789_580_009_271e4)
+            * r
+            + 2.121_379_430_158_659_7e4)
+            * r
+            + 5.394_196_021_424_751e3)
+            * r
+            + 6.871_870_074_920_579e2)
+            * r
+            + 4.231_333_070_160_091e1)
+            * r
+            + 1.0;
+        return q * num / den;
+    }
+
+    let r = if q < 0.0 { p } else { 1.0 - p };
+    let r = (-r.ln()).sqrt();
+    let val = if r <= 5.0 {
+        let r = r - 1.6;
+        let num = ((((((7.745_450_142_783_414e-4 * r + 2.272_384_498_926_918_4e-2) * r
+            + 2.417_807_251_774_506e-1)
+            * r
+            + 1.270_458_252_452_368_4)
+            * r
+            + 3.647_848_324_763_204_5)
+            * r
+            + 5.769_497_221_460_691)
+            * r
+            + 4.630_337_846_156_545)
+            * r
+            + 1.423_437_110_749_683_5;
+        let den = ((((((1.050_750_071_644_416_8e-9 * r + 5.475_938_084_995_345e-4) * r
+            + 1.519_866_656_361_645_7e-2)
+            * r
+            + 1.481_039_764_274_800_8e-1)
+            * r
+            + 6.897_673_349_851e-1)
+            * r
+            + 1.676_384_830_183_803_8)
+            * r
+            + 2.053_191_626_637_759)
+            * r
+            + 1.0;
+        num / den
+    } else {
+        let r = r - 5.0;
+        let num = ((((((2.010_334_399_292_288_1e-7 * r + 2.711_555_568_743_487_6e-5) * r
+            + 1.242_660_947_388_078_4e-3)
+            * r
+            + 2.653_218_952_657_612_4e-2)
+            * r
+            + 2.965_605_718_285_048_7e-1)
+            * r
+            + 1.784_826_539_917_291_3)
+            * r
+            + 5.463_784_911_164_114)
+            * r
+            + 6.657_904_643_501_103;
+        let den = ((((((2.044_263_103_389_939_7e-15 * r + 1.421_511_758_316_446e-7) * r
+            + 1.846_318_317_510_054_8e-5)
+            * r
+            + 7.868_691_311_456_133e-4)
+            * r
+            + 1.487_536_129_085_061_5e-2)
+            * r
+            + 1.369_298_809_227_358e-1)
+            * r
+            + 5.998_322_065_558_88e-1)
+            * r
+            + 1.0;
+        num / den
+    };
+
+    if q < 0.0 {
+        -val
+    } else {
+        val
+    }
+}
+
+/// Maximum number of terms of the series and continued fractions below.
+const MAX_TERMS: usize = 500;
+
+/// The [regularized lower incomplete gamma function][gamma] `P(a, x)`,
+/// using its series for small `x` and its continued fraction otherwise.
+///
+/// [gamma]: https://en.wikipedia.org/wiki/Incomplete_gamma_function
+pub fn gamma_p(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    } else if x.is_infinite() {
+        return 1.0;
+    }
+    let log_prefactor = a * x.ln() - x - libm::lgamma(a);
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..MAX_TERMS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * f64::EPSILON {
+                break;
+            }
+        }
+        (sum * log_prefactor.exp()).min(1.0)
+    } else {
+        1.0 - (continued_fraction(
+            |n| {
+                let n = n as f64;
+                (-n * (n - a), x + 2.0 * n + 1.0 - a)
+            },
+            x + 1.0 - a,
+        ) * log_prefactor.exp())
+        .min(1.0)
+    }
+}
+
+/// The [regularized incomplete beta function][beta] `I_x(a, b)`,
+/// using its continued fraction.
+///
+/// [beta]: https://en.wikipedia.org/wiki/Beta_function#Incomplete_beta_function
+pub fn beta_i(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    } else if x >= 1.0 {
+        return 1.0;
+    }
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - beta_i(b, a, 1.0 - x);
+    }
+    let log_prefactor = a * x.ln() + b * (1.0 - x).ln() - ln_beta(a, b);
+    let fraction = continued_fraction(
+        |n| {
+            let m = (n / 2) as f64;
+            let numerator = if n % 2 == 0 {
+                m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m))
+            } else {
+                -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0))
+            };
+            (numerator, 1.0)
+        },
+        1.0,
+    );
+    (log_prefactor.exp() * fraction / a).clamp(0.0, 1.0)
+}
+
+/// The logarithm of the [beta function](https://en.wikipedia.org/wiki/Beta_function).
+pub fn ln_beta(a: f64, b: f64) -> f64 {
+    libm::lgamma(a) + libm::lgamma(b) - libm::lgamma(a + b)
+}
+
+/// Evaluates the continued fraction `1 / (b_0 + a_1 / (b_1 + a_2 / ...))`,
+/// where `terms(n)` gives `(a_n, b_n)` for `n >= 1`, using the modified
+/// Lentz algorithm.
+fn continued_fraction<T: Fn(usize) -> (f64, f64)>(terms: T, first: f64) -> f64 {
+    let tiny = 1e-300;
+    let mut d = 1.0 / if first.abs() < tiny { tiny } else { first };
+    let mut c = 1.0 / tiny;
+    let mut fraction = d;
+    for n in 1..MAX_TERMS {
+        let (a, b) = terms(n);
+        d = b + a * d;
+        d = 1.0 / if d.abs() < tiny { tiny } else { d };
+        c = b + a / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        let delta = c * d;
+        fraction *= delta;
+        if (delta - 1.0).abs() < f64::EPSILON {
+            break;
+        }
+    }
+    fraction
+}
+
+/// Compute the lower triangular [Cholesky factor][cholesky] `L` of a
+/// symmetric, positive semi-definite matrix `A = L L^T`.
+///
+/// Returns `None` if the matrix is not square, not symmetric or not
+/// positive semi-definite.
+///
+/// [cholesky]: https://en.wikipedia.org/wiki/Cholesky_decomposition
+pub fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let scale = matrix
+        .iter()
+        .flatten()
+        .fold(0.0_f64, |acc, v| acc.max(v.abs()));
+    let eps = 1e-12 * scale.max(f64::MIN_POSITIVE);
+
+    let mut lower = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        if matrix[i].len() != n {
+            return None;
+        }
+        for j in 0..=i {
+            if (matrix[i][j] - matrix[j][i]).abs() > eps {
+                return None;
+            }
+            let sum: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            let rest = matrix[i][j] - sum;
+            if i == j {
+                if rest < -eps {
+                    return None;
+                }
+                lower[i][j] = rest.max(0.0).sqrt();
+            } else if lower[j][j] > 0.0 {
+                lower[i][j] = rest / lower[j][j];
+            } else if rest.abs() > eps {
+                return None;
+            }
+        }
+    }
+    Some(lower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_inverse_cdf_is_accurate() {
+        let cases = vec![
+            (0.5, 0.0),
+            (0.9, 1.281_551_565_544_600_4),
+            (0.975, 1.959_963_984_540_054),
+            (0.01, -2.326_347_874_040_841),
+            (0.999_999, 4.753_424_308_822_899),
+            (1e-10, -6.361_340_902_404_056),
+        ];
+        for (p, want) in cases {
+            let have = normal_inverse_cdf(p);
+            assert!((have - want).abs() < 1e-10, "{} is not {}", have, want);
+            assert!((normal_cdf(have) - p).abs() / p < 1e-9);
+        }
+    }
+
+    #[test]
+    fn cholesky_factors_matrix() {
+        let matrix = vec![
+            vec![4.0, 2.0, 0.4],
+            vec![2.0, 2.0, 0.6],
+            vec![0.4, 0.6, 1.0],
+        ];
+        let lower = cholesky(&matrix).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let product: f64 = (0..3).map(|k| lower[i][k] * lower[j][k]).sum();
+                assert!((product - matrix[i][j]).abs() < 1e-12);
+            }
+        }
+
+        assert!(cholesky(&[vec![1.0, 1.0], vec![1.0, 1.0]]).is_some());
+        assert!(cholesky(&[vec![1.0, 2.0], vec![2.0, 1.0]]).is_none());
+        assert!(cholesky(&[vec![1.0, 0.5], vec![0.0, 1.0]]).is_none());
+    }
+
+    #[test]
+    fn incomplete_functions_are_accurate() {
+        // P(1, x) = 1 - exp(-x) and I_x(1, b) = 1 - (1 - x)^b
+        for x in [0.1, 1.0, 2.5, 10.0].iter() {
+            assert!((gamma_p(1.0, *x) - (1.0 - (-x).exp())).abs() < 1e-14);
+        }
+        for x in [0.05, 0.3, 0.7, 0.99].iter() {
+            assert!((beta_i(1.0, 3.0, *x) - (1.0 - (1.0 - x).powi(3))).abs() < 1e-14);
+            assert!((beta_i(2.5, 4.0, *x) + beta_i(4.0, 2.5, 1.0 - x) - 1.0).abs() < 1e-14);
+        }
+        // P(3, 2) = 1 - 5 exp(-2)
+        assert!((gamma_p(3.0, 2.0) - (1.0 - 5.0 * (-2f64).exp())).abs() < 1e-14);
+    }
+}
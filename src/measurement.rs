@@ -0,0 +1,257 @@
+use crate::inspect::Inspect;
+use crate::law::{Law, Shape};
+use crate::symbolic::{Expr, ToExpr};
+use crate::{design, importance, Graph, Rng, Uncertain};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Describes how the tolerance of a [`Measurement`] should be interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Tolerance {
+    /// The tolerance is the standard deviation of a
+    /// [normal distribution][normal] centered on the value.
+    ///
+    /// [normal]: https://en.wikipedia.org/wiki/Normal_distribution
+    Gaussian,
+    /// The tolerance is the half-width of a
+    /// [uniform distribution][uniform] centered on the value.
+    ///
+    /// [uniform]: https://en.wikipedia.org/wiki/Continuous_uniform_distribution
+    Uniform,
+    /// The tolerance is the half-width of a symmetric
+    /// [triangular distribution][triangular] centered on the value.
+    ///
+    /// [triangular]: https://en.wikipedia.org/wiki/Triangular_distribution
+    Triangular,
+}
+
+/// Error returned when constructing an uncertain value from invalid
+/// parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ParameterError {
+    /// A parameter was infinite or NaN.
+    NotFinite,
+    /// A standard deviation or tolerance was negative.
+    NegativeSpread,
+    /// The lower bound of a range was larger than the upper bound,
+    /// or the mode of a triangular distribution was outside its bounds.
+    InvalidRange,
+    /// The dimensions of vector or matrix parameters do not agree.
+    DimensionMismatch,
+    /// A covariance or correlation matrix was not symmetric and
+    /// positive semi-definite.
+    NotPositiveDefinite,
+    /// A set of observations was empty, or their weights were invalid.
+    InvalidObservations,
+    /// A rate, scale or shape parameter was not positive.
+    NotPositive,
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::NotFinite => write!(f, "Parameters must be finite"),
+            ParameterError::NegativeSpread => {
+                write!(f, "Standard deviation or tolerance must not be negative")
+            }
+            ParameterError::InvalidRange => write!(f, "Range bounds are invalid"),
+            ParameterError::DimensionMismatch => write!(f, "Parameter dimensions do not agree"),
+            ParameterError::NotPositiveDefinite => {
+                write!(f, "Matrix must be symmetric and positive semi-definite")
+            }
+            ParameterError::InvalidObservations => {
+                write!(f, "Observations must not be empty and have valid weights")
+            }
+            ParameterError::NotPositive => write!(f, "Parameters must be positive"),
+        }
+    }
+}
+
+impl Error for ParameterError {}
+
+/// An uncertain value obtained from a measurement with a known
+/// distribution of errors.
+///
+/// This type covers the most common ways in which sensor readings and
+/// other measured values are specified, without requiring a dependency
+/// on an external crate providing distributions. All constructors
+/// return an error if the given parameters are invalid.
+///
+/// # Examples
+///
+/// Basic usage: a reading of `5.0` with a standard uncertainty of `0.1`.
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, Tolerance};
+///
+/// let x = Measurement::new(5.0, 0.1, Tolerance::Gaussian).unwrap();
+/// assert!(x.map(|x| x > 4.9).pr(0.8));
+/// ```
+///
+/// Invalid parameters are reported:
+///
+/// ```
+/// use uncertain::{Measurement, ParameterError};
+///
+/// assert_eq!(Measurement::normal(0.0, -1.0).err(), Some(ParameterError::NegativeSpread));
+/// assert_eq!(Measurement::uniform(1.0, 0.0).err(), Some(ParameterError::InvalidRange));
+/// ```
+#[derive(Debug)]
+pub struct Measurement {
+    law: Law,
+}
+
+impl Measurement {
+    /// A [normal distribution][normal] with the given mean and standard
+    /// deviation.
+    ///
+    /// [normal]: https://en.wikipedia.org/wiki/Normal_distribution
+    pub fn normal(mean: f64, std_dev: f64) -> Result<Self, ParameterError> {
+        check_finite(&[mean, std_dev])?;
+        if std_dev < 0.0 {
+            return Err(ParameterError::NegativeSpread);
+        }
+        Ok(Self {
+            law: Law::new(Shape::Normal { mean, std_dev }),
+        })
+    }
+
+    /// A [uniform distribution][uniform] over the range `[low, high]`.
+    ///
+    /// [uniform]: https://en.wikipedia.org/wiki/Continuous_uniform_distribution
+    pub fn uniform(low: f64, high: f64) -> Result<Self, ParameterError> {
+        check_finite(&[low, high])?;
+        if low > high {
+            return Err(ParameterError::InvalidRange);
+        }
+        Ok(Self {
+            law: Law::new(Shape::Uniform { low, high }),
+        })
+    }
+
+    /// A [triangular distribution][triangular] over the range `[low, high]`
+    /// with its peak at `mode`.
+    ///
+    /// [triangular]: https://en.wikipedia.org/wiki/Triangular_distribution
+    pub fn triangular(low: f64, mode: f64, high: f64) -> Result<Self, ParameterError> {
+        check_finite(&[low, mode, high])?;
+        if low > mode || mode > high {
+            return Err(ParameterError::InvalidRange);
+        }
+        Ok(Self {
+            law: Law::new(Shape::Triangular { low, mode, high }),
+        })
+    }
+
+    /// A value measured with the given tolerance, where `kind` determines
+    /// how the tolerance is interpreted (see [`Tolerance`]).
+    ///
+    /// # Examples
+    ///
+    /// A resistor with a nominal value of `100` Ohm and `5%` tolerance:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Measurement, Tolerance};
+    ///
+    /// let r = Measurement::new(100.0, 5.0, Tolerance::Uniform).unwrap();
+    /// assert!(r.map(|r| r >= 95.0 && r <= 105.0).pr(0.999));
+    /// ```
+    pub fn new(value: f64, tolerance: f64, kind: Tolerance) -> Result<Self, ParameterError> {
+        check_finite(&[value, tolerance])?;
+        if tolerance < 0.0 {
+            return Err(ParameterError::NegativeSpread);
+        }
+        match kind {
+            Tolerance::Gaussian => Self::normal(value, tolerance),
+            Tolerance::Uniform => Self::uniform(value - tolerance, value + tolerance),
+            Tolerance::Triangular => Self::triangular(value - tolerance, value, value + tolerance),
+        }
+    }
+}
+
+pub(crate) fn check_finite(params: &[f64]) -> Result<(), ParameterError> {
+    if params.iter().all(|p| p.is_finite()) {
+        Ok(())
+    } else {
+        Err(ParameterError::NotFinite)
+    }
+}
+
+impl Uncertain for Measurement {
+    type Value = f64;
+
+    fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
+        importance::value().unwrap_or_else(|| self.law.inverse_cdf(design::uniform(self, rng)))
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl Inspect for Measurement {
+    fn law(&self) -> Option<Law> {
+        Some(self.law)
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        graph.add(self.law.to_string(), Vec::new())
+    }
+}
+
+impl ToExpr for Measurement {
+    fn expr(&self) -> Expr {
+        Expr::source(self, Some(self.law), "measurement")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        assert_eq!(
+            Measurement::normal(f64::NAN, 1.0).err(),
+            Some(ParameterError::NotFinite)
+        );
+        assert_eq!(
+            Measurement::new(1.0, -0.5, Tolerance::Triangular).err(),
+            Some(ParameterError::NegativeSpread)
+        );
+        assert_eq!(
+            Measurement::triangular(0.0, 2.0, 1.0).err(),
+            Some(ParameterError::InvalidRange)
+        );
+        assert!(Measurement::normal(1.0, 0.0).is_ok());
+    }
+
+    #[test]
+    fn measurements_have_correct_mean() {
+        for kind in [
+            Tolerance::Gaussian,
+            Tolerance::Uniform,
+            Tolerance::Triangular,
+        ] {
+            let x = Measurement::new(3.0, 1.0, kind).unwrap();
+            let mu = x.expect(0.05).unwrap();
+            assert!((mu - 3.0).abs() < 0.05, "{:?} has mean {}", kind, mu);
+        }
+        let x = Measurement::triangular(0.0, 0.0, 3.0).unwrap();
+        assert!((x.expect(0.05).unwrap() - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn bounded_measurements_stay_in_bounds() {
+        let u = Measurement::new(0.0, 2.0, Tolerance::Uniform).unwrap();
+        let t = Measurement::new(0.0, 2.0, Tolerance::Triangular).unwrap();
+        assert!(u.map(|v| v.abs() <= 2.0).pr(0.9999));
+        let t = t.into_ref();
+        assert!((&t).map(|v| v.abs() <= 2.0).pr(0.9999));
+        assert!(!(&t).map(|v| v.abs() <= 1.0).pr(0.9));
+    }
+}
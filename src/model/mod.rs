@@ -0,0 +1,353 @@
+use crate::{BoxedUncertain, Shared};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+mod parser;
+
+enum Variable {
+    Number(Shared<BoxedUncertain<f64>>),
+    Boolean(Shared<BoxedUncertain<bool>>),
+}
+
+/// A query stated in a [`Model`].
+pub enum Query {
+    /// Test if the condition is true with at least the given
+    /// probability, see [`pr`](crate::Uncertain::pr).
+    Pr {
+        /// The condition, as written in the model.
+        source: String,
+        /// The condition to test.
+        event: BoxedUncertain<bool>,
+        /// The probability to test for.
+        probability: f32,
+    },
+    /// Compute the expectation of a value, see [`expect`](crate::Uncertain::expect).
+    Expect {
+        /// The expression, as written in the model.
+        source: String,
+        /// The value whose expectation should be computed.
+        value: BoxedUncertain<f64>,
+        /// The desired precision of the expectation.
+        precision: f64,
+    },
+    /// Compute a quantile of a value, see [`quantile`](crate::Uncertain::quantile).
+    Quantile {
+        /// The expression, as written in the model.
+        source: String,
+        /// The value whose quantile should be computed.
+        value: BoxedUncertain<f64>,
+        /// The quantile to compute, in `(0, 1)`.
+        quantile: f64,
+        /// The desired precision of the quantile.
+        precision: f64,
+    },
+}
+
+impl Query {
+    /// The expression this query is about, as written in the model.
+    pub fn source(&self) -> &str {
+        match self {
+            Query::Pr { source, .. }
+            | Query::Expect { source, .. }
+            | Query::Quantile { source, .. } => source,
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Pr {
+                source,
+                probability,
+                ..
+            } => write!(f, "pr({}, {})", source, probability),
+            Query::Expect {
+                source, precision, ..
+            } => write!(f, "expect({}, {})", source, precision),
+            Query::Quantile {
+                source,
+                quantile,
+                precision,
+                ..
+            } => write!(f, "quantile({}, {}, {})", source, quantile, precision),
+        }
+    }
+}
+
+/// Error returned when a model description is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, message: String) -> Self {
+        Self {
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// The line on which the error occurred, starting at one.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column at which the error occurred, starting at one.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// A description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// A model of uncertain values, described in a simple text format.
+///
+/// Every line of the description contains one statement, and
+/// comments start with `#`:
+///
+/// - `input <name> = <distribution>(<parameters>)` defines a source of
+///   uncertainty, where the distribution is one of `normal(mean, std_dev)`,
+///   `uniform(low, high)`, `triangular(low, mode, high)`, `point_mass(value)`
+///   or `empirical(values...)`, see [`Spec`](crate::Spec).
+/// - `let <name> = <expression>` defines a value computed from the values
+///   defined before it. Expressions can use numbers, the operators `+`, `-`,
+///   `*`, `/`, `<`, `<=`, `>`, `>=`, `!`, `&&`, `||`, parentheses and the
+///   functions `sqrt`, `exp`, `ln`, `sin`, `cos` and `abs`, which can be
+///   nested at most `64` times.
+/// - `pr(<condition>, <probability>)`, `expect(<expression>, <precision>)` and
+///   `quantile(<expression>, <quantile>, <precision>)` state a [`Query`] about
+///   the model.
+///
+/// Values can be used any number of times, and are shared like values
+/// created with [`into_shared`](crate::Uncertain::into_shared). Inputs are
+/// [`named`](crate::Uncertain::named) after their variable.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Model, Query};
+///
+/// let model: Model = "
+///     input speed = normal(5, 0.5)  # speed through the air
+///     input wind = uniform(-1, 1)
+///     let ground = speed - wind
+///     pr(ground > 3, 0.9)
+/// "
+/// .parse()
+/// .unwrap();
+///
+/// match &model.queries()[0] {
+///     Query::Pr { event, probability, .. } => assert!(event.pr(*probability)),
+///     _ => unreachable!(),
+/// }
+/// assert!(model.number("ground").unwrap().map(|v| v < 7.0).pr(0.9));
+/// ```
+///
+/// Errors report where they occurred:
+///
+/// ```
+/// use uncertain::Model;
+///
+/// let err = Model::parse("input x = normal(0, 1)\nlet y = x + z").err().unwrap();
+/// assert_eq!((err.line(), err.column()), (2, 13));
+/// assert_eq!(err.message(), "Unknown name `z`");
+/// ```
+pub struct Model {
+    variables: Vec<(String, Variable)>,
+    queries: Vec<Query>,
+}
+
+impl Model {
+    /// Parse a model description.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error giving the location of the first problem in the
+    /// description, for example a syntax error, an unknown name, invalid
+    /// distribution parameters or a condition used as a number.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        parser::parse(source)
+    }
+
+    fn variable(&self, name: &str) -> Option<&Variable> {
+        self.variables
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, variable)| variable)
+    }
+
+    /// The names of all inputs and values defined in the model, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.variables.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The numeric value with the given name, if there is one.
+    ///
+    /// The returned value shares its samples with all other uses of
+    /// the value in the model.
+    pub fn number(&self, name: &str) -> Option<BoxedUncertain<f64>> {
+        match self.variable(name)? {
+            Variable::Number(value) => Some(BoxedUncertain::new(value.clone())),
+            Variable::Boolean(_) => None,
+        }
+    }
+
+    /// The condition with the given name, if there is one.
+    ///
+    /// The returned value shares its samples with all other uses of
+    /// the value in the model.
+    pub fn condition(&self, name: &str) -> Option<BoxedUncertain<bool>> {
+        match self.variable(name)? {
+            Variable::Boolean(value) => Some(BoxedUncertain::new(value.clone())),
+            Variable::Number(_) => None,
+        }
+    }
+
+    /// The queries stated in the model, in order.
+    pub fn queries(&self) -> &[Query] {
+        &self.queries
+    }
+
+    /// Take the queries stated in the model, in order.
+    pub fn into_queries(self) -> Vec<Query> {
+        self.queries
+    }
+}
+
+impl FromStr for Model {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Uncertain;
+
+    #[test]
+    fn values_are_shared() {
+        let model = Model::parse(
+            "input x = normal(0, 1)
+             let zero = x - x
+             let never = x > x || !(x <= x)",
+        )
+        .unwrap();
+        assert_eq!(model.number("zero").unwrap().expect(0.1).unwrap(), 0.0);
+        assert!(!model.condition("never").unwrap().pr(0.01));
+        assert!(model.number("never").is_none());
+        assert_eq!(
+            model.names().collect::<Vec<_>>(),
+            vec!["x", "zero", "never"]
+        );
+    }
+
+    #[test]
+    fn expressions_follow_precedence() {
+        let model = Model::parse(
+            "let a = 1 + 2 * 3 - -4 / 2
+             let b = sqrt(abs(-16)) * (1 + 1)
+             expect(a + b, 0.1)",
+        )
+        .unwrap();
+        assert_eq!(model.number("a").unwrap().expect(0.1).unwrap(), 9.0);
+        assert_eq!(model.number("b").unwrap().expect(0.1).unwrap(), 8.0);
+        match &model.queries()[0] {
+            Query::Expect {
+                source,
+                value,
+                precision,
+            } => {
+                assert_eq!(source, "a + b");
+                assert_eq!(value.expect(*precision).unwrap(), 17.0);
+            }
+            _ => panic!("expected an expectation query"),
+        }
+    }
+
+    #[test]
+    fn errors_have_locations() {
+        let cases = [
+            (
+                "input x = normal(0)",
+                1,
+                11,
+                "`normal` takes 2 parameters, found 1",
+            ),
+            (
+                "input x = normal(0, -1)",
+                1,
+                11,
+                "Standard deviation or tolerance must not be negative",
+            ),
+            (
+                "input x = gamma(1, 2)",
+                1,
+                11,
+                "Unknown distribution `gamma`",
+            ),
+            ("let x = 1\nlet x = 2", 2, 5, "`x` is already defined"),
+            ("let x = (1 + 2", 1, 15, "Expected `)`, found end of line"),
+            ("let x = 1 > 0\nlet y = x + 1", 2, 9, "Expected a number"),
+            ("let x = 1 + 2\npr(x, 0.5)", 2, 4, "Expected a condition"),
+            ("pr(1 > 0, 1.5)", 1, 11, "Probability must be in (0, 1)"),
+            ("let x = 1 $ 2", 1, 11, "Unexpected character `$`"),
+            (
+                "  x = 1",
+                1,
+                3,
+                "Expected `input`, `let`, `pr`, `expect` or `quantile`, found `x`",
+            ),
+            ("quantile(1, 0.5)", 1, 16, "Expected `,`, found `)`"),
+            ("let sqrt = 1", 1, 5, "`sqrt` is a reserved name"),
+        ];
+        for (source, line, column, message) in cases.iter() {
+            let err = Model::parse(source).err().unwrap();
+            assert_eq!(err.message(), *message, "{}", source);
+            assert_eq!((err.line(), err.column()), (*line, *column), "{}", source);
+        }
+    }
+
+    #[test]
+    fn deep_nesting_is_reported() {
+        let parens = format!("let x = {}1{}", "(".repeat(65), ")".repeat(65));
+        let signs = format!("let x = 1 + {}1", "-".repeat(100_000));
+        let calls = format!("let x = {}1{}", "abs(".repeat(1000), ")".repeat(1000));
+        for (source, column) in [(parens, 73), (signs, 77), (calls, 265)] {
+            let err = Model::parse(&source).err().unwrap();
+            assert_eq!(err.message(), "Expressions can be nested at most 64 times");
+            assert_eq!((err.line(), err.column()), (1, column));
+        }
+
+        let source = format!("let x = {}1{}", "(".repeat(64), ")".repeat(64));
+        assert!(Model::parse(&source).is_ok());
+    }
+}
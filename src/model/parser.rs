@@ -0,0 +1,576 @@
+use super::{Model, ParseError, Query, Variable};
+use crate::{BoxedUncertain, PointMass, Spec, Uncertain};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Name(name) => format!("`{}`", name),
+            Token::Number(number) => format!("`{}`", number),
+            Token::Symbol(symbol) => format!("`{}`", symbol),
+        }
+    }
+}
+
+/// Symbols in order of decreasing length, so that the longest one matches first.
+const SYMBOLS: [&str; 15] = [
+    "<=", ">=", "&&", "||", "<", ">", "=", "!", "+", "-", "*", "/", "(", ")", ",",
+];
+
+const KEYWORDS: [&str; 5] = ["input", "let", "pr", "expect", "quantile"];
+
+/// How deeply parentheses, function calls and unary operators may be nested,
+/// which bounds the recursion of the parser and of the parsed values.
+const MAX_DEPTH: usize = 64;
+
+/// Split a line into tokens and the (one-based) column at which they start.
+fn tokenize(text: &str, line: usize) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let column = idx + 1;
+        let c = chars[idx];
+        if c == '#' {
+            break;
+        } else if c.is_whitespace() {
+            idx += 1;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = idx;
+            while idx < chars.len() && (chars[idx].is_ascii_alphanumeric() || chars[idx] == '_') {
+                idx += 1;
+            }
+            let name = chars[start..idx].iter().collect();
+            tokens.push((Token::Name(name), column));
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = idx;
+            while idx < chars.len() && (chars[idx].is_ascii_digit() || chars[idx] == '.') {
+                idx += 1;
+            }
+            if idx < chars.len() && (chars[idx] == 'e' || chars[idx] == 'E') {
+                idx += 1;
+                if idx < chars.len() && (chars[idx] == '+' || chars[idx] == '-') {
+                    idx += 1;
+                }
+                while idx < chars.len() && chars[idx].is_ascii_digit() {
+                    idx += 1;
+                }
+            }
+            let literal: String = chars[start..idx].iter().collect();
+            let number = literal.parse().map_err(|_| {
+                ParseError::new(line, column, format!("Invalid number `{}`", literal))
+            })?;
+            tokens.push((Token::Number(number), column));
+        } else {
+            let rest: String = chars[idx..].iter().take(2).collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .copied()
+                .ok_or_else(|| {
+                    ParseError::new(line, column, format!("Unexpected character `{}`", c))
+                })?;
+            idx += symbol.chars().count();
+            tokens.push((Token::Symbol(symbol), column));
+        }
+    }
+    Ok(tokens)
+}
+
+/// The value of a parsed expression.
+enum Term {
+    Number(BoxedUncertain<f64>),
+    Boolean(BoxedUncertain<bool>),
+}
+
+/// Parses a single line of a model, with access to the
+/// variables defined on previous lines.
+struct Parser<'a> {
+    model: &'a Model,
+    text: &'a str,
+    tokens: Vec<(Token, usize)>,
+    line: usize,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<S: Into<String>>(&self, column: usize, message: S) -> ParseError {
+        ParseError::new(self.line, column, message.into())
+    }
+
+    /// The column of the next token, or the end of the line.
+    fn column(&self) -> usize {
+        match self.tokens.get(self.pos) {
+            Some((_, column)) => *column,
+            None => self.text.chars().count() + 1,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let found = match self.peek() {
+            Some(token) => token.describe(),
+            None => "end of line".to_string(),
+        };
+        self.error(
+            self.column(),
+            format!("Expected {}, found {}", expected, found),
+        )
+    }
+
+    /// Parse a nested part of an expression, which starts at `column`.
+    fn nested<T>(
+        &mut self,
+        column: usize,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(
+                column,
+                format!("Expressions can be nested at most {} times", MAX_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(other)) if *other == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", symbol)))
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<(String, usize), ParseError> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                let column = self.column();
+                self.pos += 1;
+                Ok((name, column))
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), ParseError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected("end of line")),
+        }
+    }
+
+    /// A literal number, optionally preceded by a sign.
+    fn literal(&mut self) -> Result<f64, ParseError> {
+        let sign = if self.eat("-") { -1.0 } else { 1.0 };
+        match self.peek() {
+            Some(Token::Number(number)) => {
+                let number = *number;
+                self.pos += 1;
+                Ok(sign * number)
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    /// A comma separated list of literal numbers in parentheses.
+    fn literals(&mut self) -> Result<Vec<f64>, ParseError> {
+        self.expect_symbol("(")?;
+        let mut values = vec![self.literal()?];
+        while self.eat(",") {
+            values.push(self.literal()?);
+        }
+        self.expect_symbol(")")?;
+        Ok(values)
+    }
+
+    fn definition(&mut self) -> Result<String, ParseError> {
+        let (name, column) = self.expect_name()?;
+        if KEYWORDS.contains(&name.as_str()) || FUNCTIONS.contains(&name.as_str()) {
+            return Err(self.error(column, format!("`{}` is a reserved name", name)));
+        }
+        if self.model.variable(&name).is_some() {
+            return Err(self.error(column, format!("`{}` is already defined", name)));
+        }
+        self.expect_symbol("=")?;
+        Ok(name)
+    }
+
+    /// `input <name> = <distribution>(<parameters>)`
+    fn input(&mut self) -> Result<(String, Variable), ParseError> {
+        let name = self.definition()?;
+        let (family, column) = self.expect_name()?;
+        let params = self.literals()?;
+        self.expect_end()?;
+
+        let arity = match family.as_str() {
+            "point_mass" => Some(1),
+            "normal" | "uniform" => Some(2),
+            "triangular" => Some(3),
+            "empirical" => None,
+            _ => return Err(self.error(column, format!("Unknown distribution `{}`", family))),
+        };
+        if let Some(arity) = arity.filter(|arity| *arity != params.len()) {
+            return Err(self.error(
+                column,
+                format!(
+                    "`{}` takes {} parameters, found {}",
+                    family,
+                    arity,
+                    params.len()
+                ),
+            ));
+        }
+        let spec = match family.as_str() {
+            "point_mass" => Spec::PointMass { value: params[0] },
+            "normal" => Spec::Normal {
+                mean: params[0],
+                std_dev: params[1],
+            },
+            "uniform" => Spec::Uniform {
+                low: params[0],
+                high: params[1],
+            },
+            "triangular" => Spec::Triangular {
+                low: params[0],
+                mode: params[1],
+                high: params[2],
+            },
+            _ => Spec::Empirical {
+                values: params,
+                weights: None,
+            },
+        };
+        let value = spec
+            .build()
+            .map_err(|err| self.error(column, err.to_string()))?
+            .named(&name)
+            .into_boxed();
+        Ok((name, Variable::Number(value.into_shared())))
+    }
+
+    /// `let <name> = <expression>`
+    fn binding(&mut self) -> Result<(String, Variable), ParseError> {
+        let name = self.definition()?;
+        let value = match self.expression()? {
+            Term::Number(value) => Variable::Number(value.into_shared()),
+            Term::Boolean(value) => Variable::Boolean(value.into_shared()),
+        };
+        self.expect_end()?;
+        Ok((name, value))
+    }
+
+    /// `pr(<condition>, <probability>)`, `expect(<expression>, <precision>)`
+    /// or `quantile(<expression>, <quantile>, <precision>)`
+    fn query(&mut self, kind: &str) -> Result<Query, ParseError> {
+        self.expect_symbol("(")?;
+        let start = self.column();
+        let value = self.expression()?;
+        let end = self.column();
+        let mut params = Vec::new();
+        while self.eat(",") {
+            params.push((self.column(), self.literal()?));
+        }
+        let arity = if kind == "quantile" { 2 } else { 1 };
+        if params.len() != arity {
+            return Err(self.unexpected(if params.len() < arity { "`,`" } else { "`)`" }));
+        }
+        self.expect_symbol(")")?;
+        self.expect_end()?;
+
+        let source: String = self
+            .text
+            .chars()
+            .skip(start - 1)
+            .take(end - start)
+            .collect::<String>()
+            .trim()
+            .to_string();
+        let probability = |(column, param): (usize, f64)| {
+            if param > 0.0 && param < 1.0 {
+                Ok(param)
+            } else {
+                Err(self.error(column, "Probability must be in (0, 1)"))
+            }
+        };
+        let precision = |(column, param): (usize, f64)| {
+            if param > 0.0 {
+                Ok(param)
+            } else {
+                Err(self.error(column, "Precision must be positive"))
+            }
+        };
+        match (kind, value) {
+            ("pr", Term::Boolean(event)) => Ok(Query::Pr {
+                source,
+                event,
+                probability: probability(params[0])? as f32,
+            }),
+            ("expect", Term::Number(value)) => Ok(Query::Expect {
+                source,
+                value,
+                precision: precision(params[0])?,
+            }),
+            ("quantile", Term::Number(value)) => Ok(Query::Quantile {
+                source,
+                value,
+                quantile: probability(params[0])?,
+                precision: precision(params[1])?,
+            }),
+            ("pr", Term::Number(_)) => Err(self.error(start, "Expected a condition")),
+            (_, _) => Err(self.error(start, "Expected a number")),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Term, ParseError> {
+        let column = self.column();
+        let mut lhs = self.conjunction()?;
+        while self.eat("||") {
+            let rhs_column = self.column();
+            let rhs = self.conjunction()?;
+            let (a, b) = self.booleans((lhs, column), (rhs, rhs_column))?;
+            lhs = Term::Boolean(a.or(b).into_boxed());
+        }
+        Ok(lhs)
+    }
+
+    fn conjunction(&mut self) -> Result<Term, ParseError> {
+        let column = self.column();
+        let mut lhs = self.negation()?;
+        while self.eat("&&") {
+            let rhs_column = self.column();
+            let rhs = self.negation()?;
+            let (a, b) = self.booleans((lhs, column), (rhs, rhs_column))?;
+            lhs = Term::Boolean(a.and(b).into_boxed());
+        }
+        Ok(lhs)
+    }
+
+    fn negation(&mut self) -> Result<Term, ParseError> {
+        let start = self.column();
+        if self.eat("!") {
+            let column = self.column();
+            match self.nested(start, Self::negation)? {
+                Term::Boolean(value) => Ok(Term::Boolean(value.not().into_boxed())),
+                Term::Number(_) => Err(self.error(column, "Expected a condition")),
+            }
+        } else {
+            self.comparison()
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Term, ParseError> {
+        let column = self.column();
+        let lhs = self.sum()?;
+        let op = match self.peek() {
+            Some(Token::Symbol(op)) if ["<", "<=", ">", ">="].contains(op) => *op,
+            _ => return Ok(lhs),
+        };
+        self.pos += 1;
+        let rhs_column = self.column();
+        let rhs = self.sum()?;
+        let (a, b) = self.numbers((lhs, column), (rhs, rhs_column))?;
+        let value = match op {
+            "<" => a.lt(b).into_boxed(),
+            "<=" => a.le(b).into_boxed(),
+            ">" => a.gt(b).into_boxed(),
+            _ => a.ge(b).into_boxed(),
+        };
+        Ok(Term::Boolean(value))
+    }
+
+    fn sum(&mut self) -> Result<Term, ParseError> {
+        let column = self.column();
+        let mut lhs = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol(op)) if ["+", "-"].contains(op) => *op,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs_column = self.column();
+            let rhs = self.product()?;
+            let (a, b) = self.numbers((lhs, column), (rhs, rhs_column))?;
+            lhs = Term::Number(match op {
+                "+" => a.add(b).into_boxed(),
+                _ => a.sub(b).into_boxed(),
+            });
+        }
+    }
+
+    fn product(&mut self) -> Result<Term, ParseError> {
+        let column = self.column();
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol(op)) if ["*", "/"].contains(op) => *op,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs_column = self.column();
+            let rhs = self.unary()?;
+            let (a, b) = self.numbers((lhs, column), (rhs, rhs_column))?;
+            lhs = Term::Number(match op {
+                "*" => a.mul(b).into_boxed(),
+                _ => a.div(b).into_boxed(),
+            });
+        }
+    }
+
+    fn unary(&mut self) -> Result<Term, ParseError> {
+        let start = self.column();
+        if self.eat("-") {
+            let column = self.column();
+            match self.nested(start, Self::unary)? {
+                Term::Number(value) => Ok(Term::Number(value.map(|v: f64| -v).into_boxed())),
+                Term::Boolean(_) => Err(self.error(column, "Expected a number")),
+            }
+        } else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<Term, ParseError> {
+        let column = self.column();
+        match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                self.pos += 1;
+                Ok(Term::Number(PointMass::new(number).into_boxed()))
+            }
+            Some(Token::Symbol("(")) => {
+                self.pos += 1;
+                let value = self.nested(column, Self::expression)?;
+                self.expect_symbol(")")?;
+                Ok(value)
+            }
+            Some(Token::Name(name)) if FUNCTIONS.contains(&name.as_str()) => {
+                self.pos += 1;
+                self.expect_symbol("(")?;
+                let arg_column = self.column();
+                let arg = match self.nested(column, Self::expression)? {
+                    Term::Number(value) => value,
+                    Term::Boolean(_) => return Err(self.error(arg_column, "Expected a number")),
+                };
+                self.expect_symbol(")")?;
+                let value = match name.as_str() {
+                    "sqrt" => arg.sqrt().into_boxed(),
+                    "exp" => arg.exp().into_boxed(),
+                    "ln" => arg.ln().into_boxed(),
+                    "sin" => arg.sin().into_boxed(),
+                    "cos" => arg.cos().into_boxed(),
+                    _ => arg.abs().into_boxed(),
+                };
+                Ok(Term::Number(value))
+            }
+            Some(Token::Name(name)) => {
+                self.pos += 1;
+                match self.model.variable(&name) {
+                    Some(Variable::Number(value)) => Ok(Term::Number(value.clone().into_boxed())),
+                    Some(Variable::Boolean(value)) => Ok(Term::Boolean(value.clone().into_boxed())),
+                    None => Err(self.error(column, format!("Unknown name `{}`", name))),
+                }
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn numbers(
+        &self,
+        (lhs, lhs_column): (Term, usize),
+        (rhs, rhs_column): (Term, usize),
+    ) -> Result<(BoxedUncertain<f64>, BoxedUncertain<f64>), ParseError> {
+        match (lhs, rhs) {
+            (Term::Number(a), Term::Number(b)) => Ok((a, b)),
+            (Term::Boolean(_), _) => Err(self.error(lhs_column, "Expected a number")),
+            (_, Term::Boolean(_)) => Err(self.error(rhs_column, "Expected a number")),
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn booleans(
+        &self,
+        (lhs, lhs_column): (Term, usize),
+        (rhs, rhs_column): (Term, usize),
+    ) -> Result<(BoxedUncertain<bool>, BoxedUncertain<bool>), ParseError> {
+        match (lhs, rhs) {
+            (Term::Boolean(a), Term::Boolean(b)) => Ok((a, b)),
+            (Term::Number(_), _) => Err(self.error(lhs_column, "Expected a condition")),
+            (_, Term::Number(_)) => Err(self.error(rhs_column, "Expected a condition")),
+        }
+    }
+}
+
+const FUNCTIONS: [&str; 6] = ["sqrt", "exp", "ln", "sin", "cos", "abs"];
+
+/// Parse a model, one statement per line.
+pub(crate) fn parse(source: &str) -> Result<Model, ParseError> {
+    let mut model = Model {
+        variables: Vec::new(),
+        queries: Vec::new(),
+    };
+    for (idx, text) in source.lines().enumerate() {
+        let line = idx + 1;
+        let tokens = tokenize(text, line)?;
+        let keyword = match tokens.first() {
+            None => continue,
+            Some((Token::Name(keyword), _)) => keyword.clone(),
+            Some((token, column)) => {
+                return Err(ParseError::new(
+                    line,
+                    *column,
+                    format!("Expected a statement, found {}", token.describe()),
+                ));
+            }
+        };
+        let mut parser = Parser {
+            model: &model,
+            text,
+            tokens,
+            line,
+            pos: 1,
+            depth: 0,
+        };
+        match keyword.as_str() {
+            "input" => {
+                let variable = parser.input()?;
+                model.variables.push(variable);
+            }
+            "let" => {
+                let variable = parser.binding()?;
+                model.variables.push(variable);
+            }
+            "pr" | "expect" | "quantile" => {
+                let query = parser.query(&keyword)?;
+                model.queries.push(query);
+            }
+            _ => {
+                parser.pos = 0;
+                return Err(parser.unexpected("`input`, `let`, `pr`, `expect` or `quantile`"));
+            }
+        }
+    }
+    Ok(model)
+}
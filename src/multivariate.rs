@@ -0,0 +1,333 @@
+use crate::measurement::{check_finite, ParameterError};
+use crate::{context, design, inspect, math, Density, Rng, Uncertain};
+use rand_pcg::Pcg32;
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+/// The number of samples used to estimate the quantiles of marginals
+/// whose distribution is not known in closed form.
+const PILOT_SAMPLES: usize = 10_000;
+
+/// Independent standard normal numbers, one for every row of `lower`. Every
+/// row acts as its own source, such that the numbers follow sampling designs.
+fn standard_normals(rng: &mut Rng, lower: &[Vec<f64>]) -> Vec<f64> {
+    lower
+        .iter()
+        .map(|row| math::normal_inverse_cdf(design::uniform(row, rng)))
+        .collect()
+}
+
+fn correlate<'a>(lower: &'a [Vec<f64>], normals: &'a [f64]) -> impl Iterator<Item = f64> + 'a {
+    lower.iter().map(move |row| {
+        row.iter()
+            .zip(normals.iter())
+            .map(|(l, z)| l * z)
+            .sum::<f64>()
+    })
+}
+
+/// A vector of jointly normally distributed uncertain values.
+///
+/// Sources created from independent distributions are always sampled
+/// independently. When inputs are correlated, e.g. because two sensors
+/// share a noise source, this type can be used to model them jointly
+/// using a [multivariate normal distribution][mvn] with a given covariance matrix.
+///
+/// The individual components can be obtained by sharing the value using
+/// [`into_ref`](Uncertain::into_ref) and mapping it.
+///
+/// [mvn]: https://en.wikipedia.org/wiki/Multivariate_normal_distribution
+///
+/// # Examples
+///
+/// Basic usage: two strongly correlated readings.
+///
+/// ```
+/// use uncertain::{Uncertain, MultivariateNormal};
+///
+/// let readings = MultivariateNormal::new(
+///     vec![10.0, 10.0],
+///     vec![vec![1.0, 0.99], vec![0.99, 1.0]],
+/// )
+/// .unwrap()
+/// .into_ref();
+///
+/// let a = (&readings).map(|r| r[0]);
+/// let b = (&readings).map(|r| r[1]);
+/// assert!(a.sub(b).map(|diff| diff.abs() < 0.5).pr(0.9));
+/// ```
+#[derive(Debug)]
+pub struct MultivariateNormal {
+    mean: Vec<f64>,
+    lower: Vec<Vec<f64>>,
+}
+
+impl MultivariateNormal {
+    /// Create a new multivariate normal distribution with the given
+    /// mean vector and covariance matrix.
+    ///
+    /// Returns an error if the dimensions of `mean` and `covariance` do not agree,
+    /// or if `covariance` is not symmetric and positive semi-definite.
+    pub fn new(mean: Vec<f64>, covariance: Vec<Vec<f64>>) -> Result<Self, ParameterError> {
+        check_finite(&mean)?;
+        for row in covariance.iter() {
+            check_finite(row)?;
+        }
+        if mean.len() != covariance.len() {
+            return Err(ParameterError::DimensionMismatch);
+        }
+        if covariance.iter().any(|row| row.len() != mean.len()) {
+            return Err(ParameterError::DimensionMismatch);
+        }
+        let lower = math::cholesky(&covariance).ok_or(ParameterError::NotPositiveDefinite)?;
+        Ok(Self { mean, lower })
+    }
+}
+
+impl Uncertain for MultivariateNormal {
+    type Value = Vec<f64>;
+
+    fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
+        let normals = standard_normals(rng, &self.lower);
+        correlate(&self.lower, &normals)
+            .zip(self.mean.iter())
+            .map(|(x, mu)| x + mu)
+            .collect()
+    }
+}
+
+/// Couples uncertain values using a [Gaussian copula][copula].
+///
+/// The resulting value yields vectors, where each component follows the
+/// distribution of the corresponding marginal, but the components have
+/// the given [rank correlation][spearman].
+///
+/// Every sample maps correlated normal numbers through the inverse of the
+/// cumulative distribution functions of the marginals. For marginals whose
+/// distribution is known in closed form, like [`Measurement`](crate::Measurement)
+/// or [`Source`](crate::Source), this is exact. The quantiles of all other
+/// marginals are estimated from a fixed set of their samples, which are drawn
+/// once when the copula is created.
+///
+/// [copula]: https://en.wikipedia.org/wiki/Copula_(probability_theory)#Gaussian_copula
+/// [spearman]: https://en.wikipedia.org/wiki/Spearman%27s_rank_correlation_coefficient
+///
+/// # Examples
+///
+/// Basic usage: a uniform and a normal value which tend to be large together.
+///
+/// ```
+/// use uncertain::{Uncertain, GaussianCopula, Measurement};
+///
+/// let joint = GaussianCopula::new(
+///     vec![
+///         Measurement::uniform(0.0, 1.0).unwrap().into_boxed(),
+///         Measurement::normal(0.0, 1.0).unwrap().into_boxed(),
+///     ],
+///     vec![vec![1.0, 0.9], vec![0.9, 1.0]],
+/// )
+/// .unwrap();
+///
+/// let same_side = joint.map(|v| (v[0] > 0.5) == (v[1] > 0.0));
+/// assert!(same_side.pr(0.75));
+/// ```
+pub struct GaussianCopula {
+    marginals: Vec<Marginal>,
+    lower: Vec<Vec<f64>>,
+}
+
+/// The inverse of the cumulative distribution function of a marginal.
+enum Marginal {
+    Known(Arc<dyn Density + Send + Sync>),
+    /// The sorted samples of a marginal whose distribution is not known.
+    Sampled(Vec<f64>),
+}
+
+impl Marginal {
+    fn new<U>(marginal: &U) -> Self
+    where
+        U: Uncertain<Value = f64>,
+    {
+        if let Some(density) = inspect::density(marginal) {
+            return Marginal::Known(density);
+        }
+        let _frame = context::isolate();
+        let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+        let mut samples: Vec<f64> = (0..PILOT_SAMPLES)
+            .map(|epoch| marginal.sample(&mut rng, epoch))
+            .collect();
+        samples.sort_by(f64::total_cmp);
+        Marginal::Sampled(samples)
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        match self {
+            Marginal::Known(density) => density.inverse_cdf(p),
+            Marginal::Sampled(sorted) => {
+                let idx = (p * sorted.len() as f64) as usize;
+                sorted[idx.min(sorted.len() - 1)]
+            }
+        }
+    }
+}
+
+impl GaussianCopula {
+    /// Create a new Gaussian copula coupling the given marginals with the
+    /// given rank correlation matrix.
+    ///
+    /// Returns an error if the dimensions of `marginals` and `rank_correlation` do not
+    /// agree, if the matrix does not have a unit diagonal, or if it is not symmetric
+    /// and positive semi-definite.
+    pub fn new<U>(
+        marginals: Vec<U>,
+        rank_correlation: Vec<Vec<f64>>,
+    ) -> Result<Self, ParameterError>
+    where
+        U: Uncertain<Value = f64>,
+    {
+        if marginals.len() != rank_correlation.len()
+            || rank_correlation
+                .iter()
+                .any(|row| row.len() != marginals.len())
+        {
+            return Err(ParameterError::DimensionMismatch);
+        }
+        for (i, row) in rank_correlation.iter().enumerate() {
+            check_finite(row)?;
+            if row[i] != 1.0 || row.iter().any(|rho| rho.abs() > 1.0) {
+                return Err(ParameterError::NotPositiveDefinite);
+            }
+        }
+
+        // The Gaussian copula with linear correlation r has rank correlation
+        // rho = 6 / pi * asin(r / 2).
+        let correlation: Vec<Vec<f64>> = rank_correlation
+            .iter()
+            .map(|row| row.iter().map(|rho| 2.0 * (PI / 6.0 * rho).sin()).collect())
+            .collect();
+        let lower = math::cholesky(&correlation).ok_or(ParameterError::NotPositiveDefinite)?;
+
+        let marginals = marginals.iter().map(Marginal::new).collect();
+
+        Ok(Self { marginals, lower })
+    }
+}
+
+impl fmt::Debug for GaussianCopula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GaussianCopula")
+            .field("lower", &self.lower)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Uncertain for GaussianCopula {
+    type Value = Vec<f64>;
+
+    fn sample(&self, rng: &mut Rng, _epoch: usize) -> Self::Value {
+        let normals = standard_normals(rng, &self.lower);
+        correlate(&self.lower, &normals)
+            .zip(self.marginals.iter())
+            .map(|(z, marginal)| {
+                let uniform =
+                    math::normal_cdf(z).clamp(f64::MIN_POSITIVE, 1.0 - f64::EPSILON / 2.0);
+                marginal.inverse_cdf(uniform)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Empirical, Measurement, PointMass};
+
+    #[test]
+    fn multivariate_normal_has_correct_moments() {
+        let x = MultivariateNormal::new(vec![1.0, -2.0], vec![vec![4.0, -1.5], vec![-1.5, 1.0]])
+            .unwrap()
+            .into_ref();
+
+        let a = (&x).map(|v| v[0]);
+        let b = (&x).map(|v| v[1]);
+        assert!((a.expect(0.1).unwrap() - 1.0).abs() < 0.1);
+        assert!((b.expect(0.1).unwrap() + 2.0).abs() < 0.1);
+
+        let cov = (&x).map(|v| (v[0] - 1.0) * (v[1] + 2.0));
+        assert!((cov.expect(0.1).unwrap() + 1.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn invalid_covariance_is_rejected() {
+        let err = MultivariateNormal::new(vec![0.0], vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(err.err(), Some(ParameterError::DimensionMismatch));
+
+        let err = MultivariateNormal::new(vec![0.0, 0.0], vec![vec![1.0, 2.0], vec![2.0, 1.0]]);
+        assert_eq!(err.err(), Some(ParameterError::NotPositiveDefinite));
+
+        let marginals = vec![
+            Measurement::normal(0.0, 1.0).unwrap(),
+            Measurement::normal(0.0, 1.0).unwrap(),
+        ];
+        let err = GaussianCopula::new(marginals, vec![vec![1.0, 0.5], vec![0.5, 2.0]]);
+        assert_eq!(err.err(), Some(ParameterError::NotPositiveDefinite));
+    }
+
+    #[test]
+    fn copula_preserves_marginals() {
+        let x = GaussianCopula::new(
+            vec![
+                Measurement::uniform(2.0, 3.0).unwrap().into_boxed(),
+                Measurement::normal(5.0, 1.0).unwrap().into_boxed(),
+            ],
+            vec![vec![1.0, -0.5], vec![-0.5, 1.0]],
+        )
+        .unwrap()
+        .into_ref();
+
+        assert!((&x).map(|v| v[0] >= 2.0 && v[0] <= 3.0).pr(0.9999));
+        assert!(((&x).map(|v| v[0]).expect(0.05).unwrap() - 2.5).abs() < 0.05);
+        assert!(((&x).map(|v| v[1]).expect(0.1).unwrap() - 5.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn copula_with_full_rank_correlation_is_monotone() {
+        let x = GaussianCopula::new(
+            vec![
+                Measurement::uniform(0.0, 1.0).unwrap().into_boxed(),
+                Measurement::normal(0.0, 1.0).unwrap().into_boxed(),
+            ],
+            vec![vec![1.0, 1.0], vec![1.0, 1.0]],
+        )
+        .unwrap();
+
+        let same_side = x.map(|v| (v[0] > 0.5) == (v[1] > 0.0));
+        assert!(same_side.pr(0.99));
+    }
+
+    #[test]
+    fn copula_samples_other_marginals() {
+        let x = GaussianCopula::new(
+            vec![
+                Measurement::normal(0.0, 1.0).unwrap().into_boxed(),
+                Empirical::new(vec![1.0, 2.0, 3.0, 4.0])
+                    .unwrap()
+                    .into_boxed(),
+                PointMass::new(7.0).into_boxed(),
+            ],
+            vec![
+                vec![1.0, 0.9, 0.0],
+                vec![0.9, 1.0, 0.0],
+                vec![0.0, 0.0, 1.0],
+            ],
+        )
+        .unwrap()
+        .into_ref();
+
+        assert!((&x).map(|v| v[2] == 7.0).pr(0.9999));
+        assert!(((&x).map(|v| v[1]).expect(0.05).unwrap() - 2.5).abs() < 0.05);
+        let same_side = (&x).map(|v| (v[0] > 0.0) == (v[1] > 2.5));
+        assert!(same_side.pr(0.75));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::Rng;
+use rand::SeedableRng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Describes how queries treat samples which are `NaN` or infinite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NonFinite {
+    /// Stop and return [`Error::NonFinite`](crate::Error::NonFinite).
+    #[default]
+    Error,
+    /// Discard the sample and continue sampling. The number of discarded
+    /// samples is included in the result. Queries only stop once enough
+    /// samples were kept, regardless of how many were discarded.
+    Skip,
+    /// Use the sample, such that the result is `NaN` or infinite.
+    Propagate,
+}
+
+/// Describes how queries choose the samples they use to estimate a result.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Measurement, Options, Sampling};
+///
+/// let x = Measurement::normal(5.0, 1.0).unwrap();
+/// let options = Options::new().sampling(Sampling::Halton);
+/// let report = x.expect_with(0.01, &options).unwrap();
+/// assert!((report.value() - 5.0).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Sampling {
+    /// Draw independent random samples.
+    #[default]
+    Random,
+    /// Use a [quasi-Monte Carlo][qmc] design, where the sources of every
+    /// sample are driven by the points of a [Halton sequence][halton]. This
+    /// spreads samples more evenly than independent draws, such that estimates
+    /// of smooth, low-dimensional models converge with fewer samples.
+    ///
+    /// Every source is assigned its own dimension of the sequence, up to a
+    /// maximum of `32` sources. To estimate the error of the result, several
+    /// copies of the sequence are shifted randomly and sampled independently.
+    /// Sources which don't sample using the inverse of their cumulative
+    /// distribution function, like a [`Distribution`](crate::Distribution) of
+    /// an unknown family, can not follow the design. If such sources, or more
+    /// than `32` sources, are sampled, the query returns
+    /// [`Error::InvalidSampling`](crate::Error::InvalidSampling).
+    ///
+    /// Supported by [`expect_with`](crate::Uncertain::expect_with) and
+    /// [`quantile_with`](crate::Uncertain::quantile_with).
+    ///
+    /// [qmc]: https://en.wikipedia.org/wiki/Quasi-Monte_Carlo_method
+    /// [halton]: https://en.wikipedia.org/wiki/Halton_sequence
+    Halton,
+    /// Draw independent pairs of [antithetic][antithetic] samples, where the
+    /// sources of the second sample are reflected within their distribution,
+    /// for example `mean - x` instead of `mean + x` for a normal distribution.
+    /// For values which increase or decrease with their inputs, the errors of
+    /// the two samples of a pair tend to cancel out.
+    ///
+    /// Sources which don't sample using the inverse of their cumulative
+    /// distribution function, like a [`Distribution`](crate::Distribution) of
+    /// an unknown family, are not reflected. Supported by
+    /// [`expect_with`](crate::Uncertain::expect_with), other queries draw
+    /// independent samples.
+    ///
+    /// [antithetic]: https://en.wikipedia.org/wiki/Antithetic_variates
+    Antithetic,
+    /// Use a [Latin hypercube][lhs] design with the given number of points. The
+    /// range of every source is divided into `points` strata of equal probability,
+    /// and every stratum of every source is sampled exactly once, in a random
+    /// order which is independent between sources.
+    ///
+    /// Estimates are computed over the whole design, rather than stopping
+    /// once the desired precision is reached. To estimate the error of the result,
+    /// `8` independent designs are sampled, such that queries take `8 * points`
+    /// samples. If the estimate is not precise enough, the query returns
+    /// [`Error::NonConvergence`](crate::Error::NonConvergence). Designs without
+    /// points, or with more than `1250` points, such that queries would take
+    /// more than `10_000` samples, return
+    /// [`Error::InvalidSampling`](crate::Error::InvalidSampling).
+    ///
+    /// Sources which don't sample using the inverse of their cumulative
+    /// distribution function, like a [`Distribution`](crate::Distribution) of
+    /// an unknown family, can not follow the design, and queries which sample
+    /// them return
+    /// [`Error::InvalidSampling`](crate::Error::InvalidSampling). Supported by
+    /// [`expect_with`](crate::Uncertain::expect_with) and
+    /// [`quantile_with`](crate::Uncertain::quantile_with).
+    ///
+    /// [lhs]: https://en.wikipedia.org/wiki/Latin_hypercube_sampling
+    LatinHypercube {
+        /// The number of points of every design.
+        points: usize,
+    },
+}
+
+/// A token which can be used to cancel a running query from
+/// another thread.
+///
+/// The token is cheap to clone and all clones refer to the same
+/// cancellation state. See [`Options::cancellation`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Distribution, Error, Limit, Options, CancellationToken};
+/// use rand_distr::Normal;
+///
+/// let token = CancellationToken::new();
+/// let options = Options::new().cancellation(token.clone());
+///
+/// token.cancel();
+/// let x = Distribution::from(Normal::new(0.0, 1.0).unwrap());
+/// match x.expect_with(0.1, &options) {
+///     Err(Error::Interrupted(partial)) => assert_eq!(partial.limit(), Limit::Cancelled),
+///     _ => panic!("query was not cancelled"),
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token, which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all queries using this token. Queries stop before
+    /// taking their next sample.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// The limit which caused a query to stop early, see
+/// [`Error::Interrupted`](crate::Error::Interrupted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Limit {
+    /// The maximum number of samples was taken.
+    Samples,
+    /// The maximum duration has passed.
+    Duration,
+    /// The query was cancelled using a [`CancellationToken`].
+    Cancelled,
+}
+
+/// Options which control how queries like
+/// [`expect_with`](crate::Uncertain::expect_with) sample an uncertain value.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{NonFinite, Options};
+///
+/// let options = Options::new().non_finite(NonFinite::Skip);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub(crate) non_finite: NonFinite,
+    max_samples: Option<usize>,
+    max_duration: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+    seed: Option<u64>,
+    pub(crate) sampling: Sampling,
+}
+
+impl Options {
+    /// The default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how samples which are `NaN` or infinite are treated. By
+    /// default, they result in an error.
+    pub fn non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Stop after taking at most `samples` samples. Queries never take
+    /// more than `10_000` samples, regardless of this option.
+    pub fn max_samples(mut self, samples: usize) -> Self {
+        self.max_samples = Some(samples);
+        self
+    }
+
+    /// Stop after running for at least `duration`.
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Stop when `token` is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Seed the random number generator used to sample values. By default,
+    /// queries use a fixed seed, such that their results are reproducible.
+    /// Using different seeds makes it possible to check how much a result
+    /// depends on the random samples.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set how queries choose their samples. By default, samples are
+    /// drawn independently.
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// The random number generator a query should use.
+    pub(crate) fn rng(&self) -> Rng {
+        match self.seed {
+            Some(seed) => Rng::seed_from_u64(seed),
+            None => Rng::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7),
+        }
+    }
+
+    pub(crate) fn budget(&self) -> Budget<'_> {
+        Budget {
+            options: self,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Tracks the resources used by a running query.
+pub(crate) struct Budget<'a> {
+    options: &'a Options,
+    start: Instant,
+}
+
+impl Budget<'_> {
+    /// Check if the query may take another sample, after
+    /// taking `samples` samples so far.
+    pub(crate) fn check(&self, samples: usize) -> Result<(), Limit> {
+        let options = self.options;
+        if options.max_samples.is_some_and(|max| samples >= max) {
+            return Err(Limit::Samples);
+        }
+        if let Some(token) = &options.cancellation {
+            if token.is_cancelled() {
+                return Err(Limit::Cancelled);
+            }
+        }
+        if let Some(duration) = options.max_duration {
+            if self.start.elapsed() >= duration {
+                return Err(Limit::Duration);
+            }
+        }
+        Ok(())
+    }
+}
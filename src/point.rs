@@ -1,3 +1,4 @@
+use crate::symbolic::{Expr, ToExpr};
 use crate::{Rng, Uncertain};
 
 /// An uncertain value which always yields the same
@@ -22,7 +23,7 @@ use crate::{Rng, Uncertain};
 /// } else {
 ///     PointMass::new(1.0).into_boxed()
 /// });
-/// assert!(b.map(|b| b > 0.5).pr(0.9));
+/// assert!(b.map(|b| b > 0.5).pr(0.7));
 /// ```
 ///
 /// In most cases you can use [`map`](Uncertain::map) instead:
@@ -67,6 +68,18 @@ where
     }
 }
 
+impl ToExpr for PointMass<f64> {
+    fn expr(&self) -> Expr {
+        Expr::Constant(self.value)
+    }
+}
+
+impl ToExpr for PointMass<f32> {
+    fn expr(&self) -> Expr {
+        Expr::Constant(self.value as f64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,309 @@
+//! Estimation of small probabilities using subset simulation.
+
+use crate::error::Interrupted;
+use crate::expectation::{check_finite, Report};
+use crate::options::Budget;
+use crate::{context, design, Error, Options, Rng, Uncertain};
+
+/// Number of samples drawn for every intermediate level.
+const SAMPLES: usize = 1000;
+/// Conditional probability of every intermediate level.
+const LEVEL_PROBABILITY: f64 = 0.1;
+/// Maximum number of levels, which limits the smallest probability which
+/// can be estimated to about `1e-10`, and the number of samples to `10_000`.
+const MAX_LEVELS: usize = 11;
+/// Maximum number of samples, which all levels together never exceed.
+const MAX_SAMPLES: usize = 10_000;
+/// Correlation of the numbers drawn by the sources between
+/// consecutive states of a Markov chain.
+const CORRELATION: f64 = 0.8;
+
+/// A sample of the score, together with the numbers drawn by its sources.
+#[derive(Clone)]
+struct State {
+    score: f64,
+    point: Vec<(usize, f64)>,
+}
+
+struct Sampler<'a, U: ?Sized> {
+    src: &'a U,
+    options: &'a Options,
+    budget: Budget<'a>,
+    rng: Rng,
+    epoch: usize,
+    non_finite: usize,
+}
+
+impl<U> Sampler<'_, U>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Into<f64>,
+{
+    /// Sample the score close to `from`, or independently if `from` is empty.
+    /// Returns `None` if the sample was not finite and should be propagated.
+    /// The `partial` estimate is reported if the budget is exhausted.
+    fn sample(
+        &mut self,
+        from: &[(usize, f64)],
+        partial: Option<(f64, f64)>,
+    ) -> Result<Option<State>, Error> {
+        if let Err(limit) = self.budget.check(self.epoch) {
+            return Err(Error::Interrupted(Interrupted::new(
+                limit, self.epoch, partial,
+            )));
+        }
+        design::propose(from, CORRELATION);
+        let score = self.src.sample(&mut self.rng, self.epoch).into();
+        let point = design::drawn();
+        let epoch = self.epoch;
+        self.epoch += 1;
+        if !check_finite(score, epoch, self.options, &mut self.non_finite)? {
+            return Ok(Some(State {
+                score: f64::NEG_INFINITY,
+                point,
+            }));
+        }
+        Ok(if score.is_nan() {
+            None
+        } else {
+            Some(State { score, point })
+        })
+    }
+
+    /// The report of a non-finite sample which is propagated.
+    fn propagate(&self) -> Report<f64> {
+        Report {
+            value: f64::NAN,
+            std_error: f64::NAN,
+            samples: self.epoch,
+            non_finite: self.non_finite,
+        }
+    }
+}
+
+/// The estimate from `count` of `samples` independent samples exceeding the
+/// threshold, scaled by `probability`. If no sample exceeds the threshold,
+/// the error is bounded using the rule of three, such that the two sigma
+/// error is the upper end of the `95%` confidence interval.
+fn estimate(probability: f64, count: usize, samples: usize) -> (f64, f64) {
+    let n = samples as f64;
+    if count == 0 {
+        return (0.0, 1.5 * probability / n);
+    }
+    let fraction = count as f64 / n;
+    let std_error = (fraction * (1.0 - fraction) / n).sqrt();
+    (probability * fraction, probability * std_error)
+}
+
+/// Estimate the probability directly using independent samples, for values
+/// whose sources can not be perturbed. Continues from `count` of `samples`
+/// exceeding `threshold`, until all samples have been taken.
+fn direct<U>(
+    sampler: &mut Sampler<'_, U>,
+    threshold: f64,
+    mut count: usize,
+    mut samples: usize,
+) -> Result<Report<f64>, Error>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Into<f64>,
+{
+    while sampler.epoch < MAX_SAMPLES {
+        let partial = estimate(1.0, count, samples);
+        match sampler.sample(&[], Some(partial))? {
+            Some(state) => count += (state.score >= threshold) as usize,
+            None => return Ok(sampler.propagate()),
+        }
+        samples += 1;
+    }
+    let (value, std_error) = estimate(1.0, count, samples);
+    Ok(Report {
+        value,
+        std_error,
+        samples: sampler.epoch,
+        non_finite: sampler.non_finite,
+    })
+}
+
+/// The factor by which the variance of the fraction of samples in a chain
+/// which lie above `level` exceeds that of independent samples, using the
+/// estimator by Au and Beck (2001).
+fn correlation_factor(chains: &[Vec<f64>], level: f64, fraction: f64) -> f64 {
+    let length = chains.iter().map(Vec::len).max().unwrap_or(0);
+    let variance = fraction * (1.0 - fraction);
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    let mut factor = 0.0;
+    for lag in 1..length {
+        let mut both = 0;
+        let mut pairs = 0;
+        for chain in chains {
+            for pair in chain.windows(lag + 1) {
+                both += (pair[0] >= level && pair[lag] >= level) as usize;
+                pairs += 1;
+            }
+        }
+        let covariance = both as f64 / pairs as f64 - fraction * fraction;
+        factor += 2.0 * (1.0 - lag as f64 / length as f64) * covariance / variance;
+    }
+    factor.max(0.0)
+}
+
+/// Estimate the probability that `src` is at least `threshold` using
+/// [subset simulation](https://doi.org/10.1016/S0266-8920(01)00019-4).
+///
+/// The probability is written as a product of conditional probabilities of
+/// intermediate levels, which are chosen adaptively such that each is about
+/// [`LEVEL_PROBABILITY`]. The samples of every level are drawn using Markov
+/// chains, which start from the samples above the previous level and
+/// perturb the numbers drawn by the sources.
+pub fn compute<U>(src: &U, threshold: f64, options: &Options) -> Result<Report<f64>, Error>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Into<f64>,
+{
+    let _frame = context::isolate();
+    let _guard = design::conditional();
+    let mut sampler = Sampler {
+        src,
+        options,
+        budget: options.budget(),
+        rng: options.rng(),
+        epoch: 0,
+        non_finite: 0,
+    };
+    let seeds = (SAMPLES as f64 * LEVEL_PROBABILITY) as usize;
+
+    let mut chains = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        match sampler.sample(&[], None)? {
+            Some(state) => chains.push(vec![state]),
+            None => return Ok(sampler.propagate()),
+        }
+    }
+    if chains.iter().all(|chain| chain[0].point.is_empty()) {
+        // None of the sources draw numbers which can be perturbed
+        let reached = chains
+            .iter()
+            .filter(|chain| chain[0].score >= threshold)
+            .count();
+        return direct(&mut sampler, threshold, reached, SAMPLES);
+    }
+
+    let mut probability = 1.0;
+    // The squared coefficient of variation of the estimate
+    let mut variation = 0.0;
+    for level in 0..MAX_LEVELS {
+        let chain_scores: Vec<Vec<f64>> = chains
+            .iter()
+            .map(|chain| chain.iter().map(|state| state.score).collect())
+            .collect();
+        let mut states: Vec<State> = chains.into_iter().flatten().collect();
+        states.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        let reached = states
+            .iter()
+            .filter(|state| state.score >= threshold)
+            .count();
+        let (bound, count) = if reached >= seeds || level + 1 == MAX_LEVELS {
+            (threshold, reached)
+        } else {
+            let bound = states[seeds - 1].score;
+            let count = states.iter().filter(|state| state.score >= bound).count();
+            (bound, count)
+        };
+
+        let fraction = count as f64 / states.len() as f64;
+        let factor = correlation_factor(&chain_scores, bound, fraction);
+        if count == 0 {
+            let (value, std_error) = estimate(probability, count, states.len());
+            return Ok(Report {
+                value,
+                std_error,
+                samples: sampler.epoch,
+                non_finite: sampler.non_finite,
+            });
+        }
+        probability *= fraction;
+        variation += (1.0 - fraction) / (fraction * states.len() as f64) * (1.0 + factor);
+
+        if bound == threshold {
+            return Ok(Report {
+                value: probability,
+                std_error: probability * variation.sqrt(),
+                samples: sampler.epoch,
+                non_finite: sampler.non_finite,
+            });
+        }
+
+        // Every chain starts from one of the highest samples
+        states.truncate(seeds);
+        let starts = states;
+        chains = Vec::with_capacity(starts.len());
+        let mut moves = 0;
+        for (idx, start) in starts.iter().enumerate() {
+            let length = SAMPLES / starts.len() + (idx < SAMPLES % starts.len()) as usize;
+            let mut chain = vec![start.clone()];
+            while chain.len() < length {
+                let current = chain.last().unwrap();
+                let partial = Some((probability, probability * variation.sqrt()));
+                let next = match sampler.sample(&current.point, partial)? {
+                    Some(next) if next.score >= bound => {
+                        moves += 1;
+                        next
+                    }
+                    Some(_) => current.clone(),
+                    None => return Ok(sampler.propagate()),
+                };
+                chain.push(next);
+            }
+            chains.push(chain);
+        }
+        if moves == 0 {
+            // The chains are stuck, such that their samples say nothing
+            // about the next level. Start over with independent samples.
+            return direct(&mut sampler, threshold, 0, 0);
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math, Distribution, Measurement};
+    use rand_distr::Exp;
+
+    #[test]
+    fn tail_probability_of_normal_distribution() {
+        let x = Measurement::normal(0.0, 1.0).unwrap();
+        let y = Measurement::normal(0.0, 1.0).unwrap();
+        let score = x.add(y).map(|sum| sum / 2f64.sqrt());
+
+        let exact = 1.0 - math::normal_cdf(5.0);
+        let report = compute(&score, 5.0, &Options::new()).unwrap();
+        assert!(report.relative_error() < 1.0);
+        assert!((report.value() - exact).abs() < report.two_sigma_error());
+        assert!(report.samples() < 10 * SAMPLES);
+    }
+
+    #[test]
+    fn impossible_events_have_zero_probability() {
+        let x = Measurement::uniform(0.0, 1.0).unwrap();
+        let report = compute(&x, 2.0, &Options::new()).unwrap();
+        assert_eq!(report.value(), 0.0);
+        assert!(report.two_sigma_error() > 0.0 && report.two_sigma_error() < 1e-10);
+        assert!(report.samples() <= MAX_SAMPLES);
+    }
+
+    #[test]
+    fn sources_which_can_not_be_perturbed_are_sampled_directly() {
+        let x = Distribution::from(Exp::new(1.0).unwrap());
+        for threshold in [7.0, 10.0] {
+            let exact = f64::exp(-threshold);
+            let report = compute(&x, threshold, &Options::new()).unwrap();
+            assert_eq!(report.samples(), MAX_SAMPLES);
+            assert!((report.value() - exact).abs() < report.two_sigma_error());
+        }
+    }
+}
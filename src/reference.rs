@@ -1,4 +1,6 @@
-use crate::{Rng, Uncertain};
+use crate::inspect::{self, Inspect};
+use crate::symbolic::{Expr, Symbolic, ToExpr};
+use crate::{Graph, Rng, Uncertain};
 use std::cell::Cell;
 
 pub struct RefUncertain<U>
@@ -38,6 +40,21 @@ where
         self.cache.set(Some((epoch, value.clone())));
         value
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for &RefUncertain<U>
+where
+    U: Uncertain,
+    U::Value: Clone,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let address = *self as *const RefUncertain<U> as usize;
+        graph.shared(address, |graph| inspect::visit(&self.uncertain, graph))
+    }
 }
 
 impl<U> Uncertain for RefUncertain<U>
@@ -50,6 +67,40 @@ where
     fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
         <&Self as Uncertain>::sample(&self, rng, epoch)
     }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for RefUncertain<U>
+where
+    U: Uncertain,
+    U::Value: Clone,
+{
+    fn visit(&self, graph: &mut Graph) -> usize {
+        <&Self as Inspect>::visit(&self, graph)
+    }
+}
+
+impl<U> ToExpr for &RefUncertain<U>
+where
+    U: Symbolic,
+    U::Value: Clone,
+{
+    fn expr(&self) -> Expr {
+        self.uncertain.expr()
+    }
+}
+
+impl<U> ToExpr for RefUncertain<U>
+where
+    U: Symbolic,
+    U::Value: Clone,
+{
+    fn expr(&self) -> Expr {
+        self.uncertain.expr()
+    }
 }
 
 #[cfg(test)]
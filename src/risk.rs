@@ -0,0 +1,157 @@
+//! Risk measures of the tail of a distribution.
+
+use crate::expectation::{self, cast, order_statistic, quantile_rank, sort, Report};
+use crate::{Error, Options, Sampling, Uncertain};
+use num_traits::{identities, Float};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The fewest samples in the tail for which an expected
+/// shortfall estimate is considered reliable.
+const MIN_TAIL_SAMPLES: usize = 10;
+
+/// The result of a risk measure, see
+/// [`value_at_risk_with`](Uncertain::value_at_risk_with).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RiskReport<F>
+where
+    F: Float,
+{
+    report: Report<F>,
+    tail_samples: usize,
+}
+
+impl<F: Float> RiskReport<F> {
+    /// The estimated value.
+    pub fn value(&self) -> F {
+        self.report.value()
+    }
+
+    /// The two sigma confidence interval around the estimated value,
+    /// see [`Report::two_sigma_error`].
+    pub fn two_sigma_error(&self) -> F {
+        self.report.two_sigma_error()
+    }
+
+    /// The number of samples which were used to compute the estimate.
+    pub fn samples(&self) -> usize {
+        self.report.samples()
+    }
+
+    /// The number of samples at or above the value at risk, which are
+    /// the samples the estimate of the tail is based on. If this is
+    /// small, the estimate should be used with caution.
+    pub fn tail_samples(&self) -> usize {
+        self.tail_samples
+    }
+
+    /// The number of samples which were `NaN` or infinite.
+    pub fn non_finite_samples(&self) -> usize {
+        self.report.non_finite_samples()
+    }
+}
+
+/// The expected shortfall of sorted samples at the level `alpha`. Returns
+/// the estimate, its standard error and the number of samples in the tail.
+fn shortfall<F: Float>(sorted: &[F], alpha: f64) -> (F, F, usize) {
+    let var = order_statistic(sorted, quantile_rank(alpha, sorted.len()));
+    let tail = &sorted[sorted.partition_point(|sample| *sample < var)..];
+    let count: F = cast(tail.len());
+    let mean = tail
+        .iter()
+        .fold(identities::zero(), |sum: F, sample| sum + *sample)
+        / count;
+    if tail.len() < MIN_TAIL_SAMPLES {
+        return (mean, F::infinity(), tail.len());
+    }
+    let spread = tail.iter().fold(identities::zero(), |sum: F, sample| {
+        sum + (*sample - mean) * (*sample - mean)
+    }) / (count - identities::one());
+    // The asymptotic variance of the estimator, which includes the
+    // uncertainty of the value at risk, see Brazauskas et al. (2008).
+    let alpha: F = F::from(alpha).unwrap();
+    let n: F = cast(sorted.len());
+    let variance =
+        (spread + alpha * (mean - var) * (mean - var)) / (n * (identities::one::<F>() - alpha));
+    (mean, variance.sqrt(), tail.len())
+}
+
+impl<F: Float> RiskReport<F> {
+    /// The value at risk at the level `alpha`, given the `report` of the
+    /// corresponding quantile.
+    pub(crate) fn value_at_risk(report: Report<F>, alpha: f64) -> Self {
+        let samples = report.samples();
+        let rank = quantile_rank(alpha, samples).ceil().max(0.0) as usize;
+        Self {
+            report,
+            tail_samples: samples.saturating_sub(rank),
+        }
+    }
+}
+
+/// Compute the expected shortfall at the level `alpha`,
+/// using the same sampling as [`expectation::quantile`].
+pub(crate) fn expected_shortfall<U>(
+    src: &U,
+    alpha: f64,
+    precision: U::Value,
+    options: &Options,
+) -> Result<RiskReport<U::Value>, Error<U::Value>>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Float,
+{
+    if matches!(
+        options.sampling,
+        Sampling::Halton | Sampling::LatinHypercube { .. }
+    ) {
+        let statistic = |samples: &[U::Value]| {
+            let (value, _, tail) = shortfall(&sort(samples), alpha);
+            (value, tail)
+        };
+        let (report, tails) = expectation::compute_replicated(src, precision, options, statistic)?;
+        Ok(RiskReport {
+            report,
+            tail_samples: tails.iter().sum(),
+        })
+    } else {
+        let statistic = |sorted: &[U::Value]| shortfall(sorted, alpha);
+        let (report, tail_samples) =
+            expectation::compute_sorted(src, precision, options, statistic)?;
+        Ok(RiskReport {
+            report,
+            tail_samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math, Measurement};
+
+    #[test]
+    fn risk_measures_of_normal_distribution() {
+        let x = Measurement::normal(0.0, 1.0).unwrap();
+        let z = math::normal_inverse_cdf(0.95);
+        let shortfall = math::normal_pdf(z) / 0.05;
+
+        let var = x.value_at_risk_with(0.95, 0.1, &Options::new()).unwrap();
+        assert!((var.value() - z).abs() < 0.1);
+        let es = expected_shortfall(&x, 0.95, 0.1, &Options::new()).unwrap();
+        assert!((es.value() - shortfall).abs() < 0.1);
+        assert!(es.value() > var.value());
+        assert!(es.tail_samples() >= MIN_TAIL_SAMPLES);
+        let fraction = es.tail_samples() as f64 / es.samples() as f64;
+        assert!((fraction - 0.05).abs() < 0.02);
+
+        let options = Options::new().sampling(Sampling::LatinHypercube { points: 1000 });
+        let es = expected_shortfall(&x, 0.95, 0.05, &options).unwrap();
+        assert!((es.value() - shortfall).abs() < 0.05);
+        let var = x.value_at_risk_with(0.95, 0.05, &options).unwrap();
+        let quantile = x.quantile_with(0.95, 0.05, &options).unwrap();
+        assert_eq!(var.value(), quantile.value());
+        assert!(es.value() > var.value());
+    }
+}
@@ -0,0 +1,321 @@
+//! Variance based sensitivity analysis.
+
+use crate::context::{self, Context};
+use crate::{Rng, Uncertain};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Which of the two independent sample matrices of the
+/// Saltelli scheme an input is drawn from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Matrix {
+    A,
+    B,
+}
+
+/// The inputs which are currently being sampled by
+/// [`compute`], see [`input`].
+pub(crate) struct Inputs {
+    names: Vec<String>,
+    /// The epochs in which the current row of both matrices was evaluated.
+    epochs: [usize; 2],
+    matrix: Matrix,
+    resampled: Option<usize>,
+}
+
+fn slot(ctx: &mut Context) -> &mut Option<Inputs> {
+    &mut ctx.sensitivity
+}
+
+/// 64 bit [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function)
+/// hash, used to derive a stable seed from the name of an input.
+pub(crate) fn hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// If a sensitivity analysis is in progress, returns the random number
+/// generator and epoch which the named input should use to produce its
+/// sample.
+///
+/// Inputs with the same name are treated as the same input. An input
+/// which is frozen to a row of a matrix is sampled in the epoch in which
+/// that row was evaluated, from a generator seeded by its name and that
+/// epoch, such that it yields the same value as in that evaluation.
+pub(crate) fn input(name: &str) -> Option<(Rng, usize)> {
+    context::with(|ctx| {
+        let ctx = ctx.sensitivity.as_mut()?;
+        let idx = match ctx.names.iter().position(|other| other == name) {
+            Some(idx) => idx,
+            None => {
+                ctx.names.push(name.to_string());
+                ctx.names.len() - 1
+            }
+        };
+        let matrix = if ctx.resampled == Some(idx) {
+            Matrix::B
+        } else {
+            ctx.matrix
+        };
+        let epoch = ctx.epochs[matrix as usize];
+        let seed = hash(name) ^ (epoch as u64).wrapping_mul(0x9e3779b97f4a7c15);
+        Some((Pcg32::seed_from_u64(seed), epoch))
+    })
+}
+
+/// Error returned by [`sobol_indices`](Uncertain::sobol_indices) if
+/// the indices can not be estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensitivityError {
+    /// The number of samples was `0`.
+    NoSamples,
+    /// The value did not vary between samples, such that no
+    /// input explains any of its variance.
+    ConstantOutput,
+    /// The value was `NaN` or infinite in some samples, such
+    /// that its variance is unknown.
+    NonFinite,
+}
+
+impl fmt::Display for SensitivityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensitivityError::NoSamples => write!(f, "Number of samples must be larger than 0"),
+            SensitivityError::ConstantOutput => write!(f, "Value has no variance"),
+            SensitivityError::NonFinite => write!(f, "Encountered non-finite samples"),
+        }
+    }
+}
+
+impl Error for SensitivityError {}
+
+/// First order and total [Sobol indices][sobol] of the named
+/// inputs of an uncertain value, see
+/// [`sobol_indices`](Uncertain::sobol_indices).
+///
+/// [sobol]: https://en.wikipedia.org/wiki/Variance-based_sensitivity_analysis
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SobolIndices {
+    names: Vec<String>,
+    first_order: Vec<f64>,
+    total: Vec<f64>,
+}
+
+impl SobolIndices {
+    /// The names of all inputs, in the order they were first sampled.
+    pub fn inputs(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The fraction of the variance of the output which is caused by
+    /// the input `name` alone. Returns `None` if there is no such input.
+    pub fn first_order(&self, name: &str) -> Option<f64> {
+        let idx = self.names.iter().position(|other| other == name)?;
+        Some(self.first_order[idx])
+    }
+
+    /// The fraction of the variance of the output which is caused by
+    /// the input `name`, including all interactions with other inputs.
+    /// Returns `None` if there is no such input.
+    pub fn total(&self, name: &str) -> Option<f64> {
+        let idx = self.names.iter().position(|other| other == name)?;
+        Some(self.total[idx])
+    }
+}
+
+/// Estimate Sobol indices using the estimators by Saltelli et al. (2010)
+/// for the first order and by Jansen (1999) for the total indices.
+pub fn compute<U>(src: &U, samples: usize) -> Result<SobolIndices, SensitivityError>
+where
+    U: Uncertain + ?Sized,
+    U::Value: Into<f64>,
+{
+    if samples == 0 {
+        return Err(SensitivityError::NoSamples);
+    }
+    let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+    let mut epoch = 0;
+    let _frame = context::isolate();
+    let _guard = context::enter(
+        slot,
+        Inputs {
+            names: Vec::new(),
+            epochs: [0; 2],
+            matrix: Matrix::A,
+            resampled: None,
+        },
+    );
+
+    let mut evaluate = |matrix: Matrix, resampled: Option<usize>| {
+        epoch += 1;
+        context::with(|ctx| {
+            let ctx = ctx.sensitivity.as_mut().unwrap();
+            if resampled.is_none() {
+                ctx.epochs[matrix as usize] = epoch;
+            }
+            ctx.matrix = matrix;
+            ctx.resampled = resampled;
+        });
+        src.sample(&mut rng, epoch).into()
+    };
+
+    let inputs = || context::with(|ctx| ctx.sensitivity.as_ref().unwrap().names.len());
+    let mut mean = 0.0;
+    let mut var_sum = 0.0;
+    let mut first_order = Vec::new();
+    let mut changes = Vec::new();
+    let mut total = Vec::new();
+    for row in 0..samples {
+        let a = evaluate(Matrix::A, None);
+        let b = evaluate(Matrix::B, None);
+
+        for (n, value) in [a, b].iter().enumerate() {
+            let delta = value - mean;
+            mean += delta / (2 * row + n + 1) as f64;
+            var_sum += delta * (value - mean);
+        }
+        // Inputs may be found in any evaluation, for example if they are only
+        // sampled in some branches. Before that, resampling them could not
+        // have changed the value, such that their sums start at zero.
+        let mut i = 0;
+        while i < inputs() {
+            if i == first_order.len() {
+                first_order.push(0.0);
+                changes.push(0.0);
+                total.push(0.0);
+            }
+            let ab = evaluate(Matrix::A, Some(i));
+            first_order[i] += b * (ab - a);
+            changes[i] += ab - a;
+            total[i] += (a - ab) * (a - ab) / 2.0;
+            i += 1;
+        }
+    }
+
+    let var = var_sum / (2 * samples) as f64;
+    if !var.is_finite() {
+        return Err(SensitivityError::NonFinite);
+    }
+    if var == 0.0 {
+        return Err(SensitivityError::ConstantOutput);
+    }
+    // Centering the output does not change the first order indices,
+    // but reduces the variance of their estimates.
+    for (sum, change) in first_order.iter_mut().zip(changes) {
+        *sum -= mean * change;
+    }
+    let names = context::with(|ctx| std::mem::take(&mut ctx.sensitivity.as_mut().unwrap().names));
+    let scale = |sums: Vec<f64>| {
+        sums.into_iter()
+            .map(|sum| sum / samples as f64 / var)
+            .collect()
+    };
+    Ok(SobolIndices {
+        names,
+        first_order: scale(first_order),
+        total: scale(total),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Measurement, PointMass};
+
+    #[test]
+    fn linear_model_has_correct_indices() {
+        let a = Measurement::normal(0.0, 1.0).unwrap().named("a");
+        let b = Measurement::normal(5.0, 2.0).unwrap().named("b");
+        let y = a.add(b);
+
+        let indices = y.sobol_indices(10_000).unwrap();
+        assert_eq!(indices.inputs(), &["a", "b"]);
+        assert!((indices.first_order("a").unwrap() - 0.2).abs() < 0.05);
+        assert!((indices.first_order("b").unwrap() - 0.8).abs() < 0.05);
+        assert!((indices.total("a").unwrap() - 0.2).abs() < 0.05);
+        assert!((indices.total("b").unwrap() - 0.8).abs() < 0.05);
+        assert_eq!(indices.total("c"), None);
+    }
+
+    #[test]
+    fn interactions_only_contribute_to_total_indices() {
+        let a = Measurement::uniform(-1.0, 1.0).unwrap().named("a");
+        let b = Measurement::uniform(-1.0, 1.0).unwrap().named("b");
+        let y = a.mul(b).add(PointMass::new(1.0));
+
+        let indices = y.sobol_indices(10_000).unwrap();
+        assert!(indices.first_order("a").unwrap().abs() < 0.05);
+        assert!((indices.total("a").unwrap() - 1.0).abs() < 0.05);
+        assert!((indices.total("b").unwrap() - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn shared_inputs_are_one_input() {
+        let a = || Measurement::normal(0.0, 1.0).unwrap().named("a");
+        let b = Measurement::normal(5.0, 2.0).unwrap().named("b");
+        let y = a().add(b).add(a()).common_inputs();
+
+        // y = 2a + b, such that both inputs explain half of the variance
+        let indices = y.sobol_indices(10_000).unwrap();
+        assert_eq!(indices.inputs(), &["a", "b"]);
+        assert!((indices.first_order("a").unwrap() - 0.5).abs() < 0.05);
+        assert!((indices.total("b").unwrap() - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn degenerate_outputs_are_reported() {
+        let a = Measurement::normal(0.0, 1.0).unwrap().named("a");
+        assert_eq!(a.sobol_indices(0), Err(SensitivityError::NoSamples));
+
+        let constant = a.map(|_| 1.0);
+        assert_eq!(
+            constant.sobol_indices(100),
+            Err(SensitivityError::ConstantOutput)
+        );
+        let infinite = constant.map(|x: f64| x / 0.0);
+        assert_eq!(
+            infinite.sobol_indices(100),
+            Err(SensitivityError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn shared_values_within_inputs_are_frozen() {
+        let x = Measurement::normal(0.0, 1.0).unwrap().into_ref();
+        let a = (&x).map(|x| x * x).named("a");
+        let b = Measurement::normal(0.0, 1.0).unwrap().named("b");
+        let y = a.add(b).add((&x).named("a").map(|x| x * x));
+
+        // y = 2x^2 + b, where var(2x^2) = 8
+        let indices = y.sobol_indices(10_000).unwrap();
+        assert_eq!(indices.inputs(), &["a", "b"]);
+        assert!((indices.total("a").unwrap() - 8.0 / 9.0).abs() < 0.05);
+        assert!((indices.first_order("b").unwrap() - 1.0 / 9.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn inputs_in_rare_branches_have_indices() {
+        let a = Measurement::uniform(0.0, 1.0).unwrap().named("a");
+        let y = a.flat_map(|a| {
+            let b = Measurement::normal(0.0, 1.0).unwrap().named("b");
+            let b = if a < 0.02 {
+                b.into_boxed()
+            } else {
+                PointMass::new(0.0).into_boxed()
+            };
+            b.map(move |b| a + b)
+        });
+
+        let indices = y.sobol_indices(2_000).unwrap();
+        assert_eq!(indices.inputs(), &["a", "b"]);
+        // var(y) = 1 / 12 + 0.02, of which b explains 0.02
+        let total = indices.total("b").unwrap();
+        assert!((total - 0.02 / (1.0 / 12.0 + 0.02)).abs() < 0.1);
+    }
+}
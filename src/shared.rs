@@ -0,0 +1,128 @@
+use crate::inspect::{self, Inspect};
+use crate::law::Law;
+use crate::symbolic::{Expr, Symbolic, ToExpr};
+use crate::{Density, Graph, Rng, Uncertain};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+struct State<U>
+where
+    U: Uncertain,
+{
+    uncertain: U,
+    cache: Option<(usize, U::Value)>,
+}
+
+/// An uncertain value which can be reused by cloning it.
+///
+/// This works like [`into_ref`](Uncertain::into_ref), but instead of
+/// implementing [`Uncertain`] for references, every clone owns a handle to
+/// the same cached value. This makes it possible to reuse a value in
+/// graphs which need to own their inputs, like
+/// [`BoxedUncertain`](crate::BoxedUncertain).
+/// See [`into_shared`](Uncertain::into_shared).
+pub struct Shared<U>
+where
+    U: Uncertain,
+{
+    state: Arc<Mutex<State<U>>>,
+}
+
+impl<U> Shared<U>
+where
+    U: Uncertain,
+    U::Value: Clone,
+{
+    pub(crate) fn new(contained: U) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                uncertain: contained,
+                cache: None,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<U>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<U> Clone for Shared<U>
+where
+    U: Uncertain,
+{
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<U> Uncertain for Shared<U>
+where
+    U: Uncertain,
+    U::Value: Clone,
+{
+    type Value = U::Value;
+
+    fn sample(&self, rng: &mut Rng, epoch: usize) -> Self::Value {
+        let mut state = self.lock();
+        match &state.cache {
+            Some((cache_epoch, cache_value)) if *cache_epoch == epoch => cache_value.clone(),
+            _ => {
+                let value = state.uncertain.sample(rng, epoch);
+                state.cache = Some((epoch, value.clone()));
+                value
+            }
+        }
+    }
+
+    fn inspect(&self) -> Option<&dyn Inspect> {
+        Some(self)
+    }
+}
+
+impl<U> Inspect for Shared<U>
+where
+    U: Uncertain,
+    U::Value: Clone,
+{
+    fn law(&self) -> Option<Law> {
+        inspect::law(&self.lock().uncertain)
+    }
+
+    fn density(&self) -> Option<Arc<dyn Density + Send + Sync>> {
+        inspect::density(&self.lock().uncertain)
+    }
+
+    fn visit(&self, graph: &mut Graph) -> usize {
+        let address = Arc::as_ptr(&self.state) as usize;
+        graph.shared(address, |graph| {
+            inspect::visit(&self.lock().uncertain, graph)
+        })
+    }
+}
+
+impl<U> ToExpr for Shared<U>
+where
+    U: Symbolic,
+    U::Value: Clone,
+{
+    fn expr(&self) -> Expr {
+        self.lock().uncertain.expr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Measurement, Uncertain};
+
+    #[test]
+    fn clones_share_samples() {
+        let x = Measurement::normal(0.0, 1.0).unwrap().into_shared();
+        let zero = x.clone().sub(x.clone()).into_boxed();
+        assert_eq!(zero.expect(0.1).unwrap(), 0.0);
+        assert_eq!(zero.describe().nodes().len(), 2);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{BoxedUncertain, Empirical, Measurement, ParameterError, PointMass, Uncertain};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A specification of one of the built-in sources of uncertain values.
+///
+/// Specifications are plain data, which can be stored e.g. as part of a
+/// configuration file and used to create uncertain values using
+/// [`build`](Self::build). With the `serde` feature enabled, specifications
+/// can be serialized and deserialized, where the kind of source is given
+/// by the `type` field.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use uncertain::{Uncertain, Spec};
+///
+/// let spec = Spec::Uniform { low: 1.0, high: 2.0 };
+/// let x = spec.build().unwrap();
+/// assert!(x.map(|x| x >= 1.0 && x <= 2.0).pr(0.999));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Spec {
+    /// A fixed value, see [`PointMass`].
+    PointMass {
+        /// The value.
+        value: f64,
+    },
+    /// See [`Measurement::normal`].
+    Normal {
+        /// The mean of the distribution.
+        mean: f64,
+        /// The standard deviation of the distribution.
+        std_dev: f64,
+    },
+    /// See [`Measurement::uniform`].
+    Uniform {
+        /// The lower bound of the range.
+        low: f64,
+        /// The upper bound of the range.
+        high: f64,
+    },
+    /// See [`Measurement::triangular`].
+    Triangular {
+        /// The lower bound of the range.
+        low: f64,
+        /// The peak of the distribution.
+        mode: f64,
+        /// The upper bound of the range.
+        high: f64,
+    },
+    /// A set of observations, see [`Empirical`].
+    Empirical {
+        /// The observed values.
+        values: Vec<f64>,
+        /// The weight of each value, if the values are not
+        /// equally likely.
+        #[cfg_attr(feature = "serde", serde(default))]
+        weights: Option<Vec<f64>>,
+    },
+}
+
+impl Spec {
+    /// Create the uncertain value described by this specification.
+    ///
+    /// Returns an error if the parameters of the specification are invalid.
+    pub fn build(&self) -> Result<BoxedUncertain<f64>, ParameterError> {
+        let value = match self {
+            Spec::PointMass { value } => PointMass::new(*value).into_boxed(),
+            Spec::Normal { mean, std_dev } => Measurement::normal(*mean, *std_dev)?.into_boxed(),
+            Spec::Uniform { low, high } => Measurement::uniform(*low, *high)?.into_boxed(),
+            Spec::Triangular { low, mode, high } => {
+                Measurement::triangular(*low, *mode, *high)?.into_boxed()
+            }
+            Spec::Empirical { values, weights } => {
+                let empirical = match weights {
+                    None => Empirical::new(values.clone()),
+                    Some(weights) if weights.len() == values.len() => Empirical::weighted(
+                        values
+                            .iter()
+                            .cloned()
+                            .zip(weights.iter().cloned())
+                            .collect(),
+                    ),
+                    Some(_) => return Err(ParameterError::DimensionMismatch),
+                };
+                empirical
+                    .map_err(|_| ParameterError::InvalidObservations)?
+                    .into_boxed()
+            }
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        let spec = Spec::Normal {
+            mean: 0.0,
+            std_dev: -1.0,
+        };
+        assert_eq!(spec.build().err(), Some(ParameterError::NegativeSpread));
+
+        let spec = Spec::Empirical {
+            values: vec![],
+            weights: None,
+        };
+        assert_eq!(
+            spec.build().err(),
+            Some(ParameterError::InvalidObservations)
+        );
+
+        let spec = Spec::Empirical {
+            values: vec![1.0, 2.0],
+            weights: Some(vec![1.0]),
+        };
+        assert_eq!(spec.build().err(), Some(ParameterError::DimensionMismatch));
+    }
+
+    #[test]
+    fn specs_build_sources() {
+        let spec = Spec::Empirical {
+            values: vec![1.0, 2.0],
+            weights: Some(vec![0.0, 1.0]),
+        };
+        assert!(spec.build().unwrap().map(|v| v == 2.0).pr(0.999));
+
+        let spec = Spec::PointMass { value: 3.0 };
+        assert_eq!(spec.build().unwrap().expect(0.1).unwrap(), 3.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn specs_are_serializable() {
+        let json = r#"[
+            {"type": "normal", "mean": 1.0, "std_dev": 2.0},
+            {"type": "point_mass", "value": 3.0},
+            {"type": "empirical", "values": [1.0, 2.0]}
+        ]"#;
+        let specs: Vec<Spec> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            specs,
+            vec![
+                Spec::Normal {
+                    mean: 1.0,
+                    std_dev: 2.0
+                },
+                Spec::PointMass { value: 3.0 },
+                Spec::Empirical {
+                    values: vec![1.0, 2.0],
+                    weights: None
+                },
+            ]
+        );
+
+        let json = serde_json::to_string(&specs[0]).unwrap();
+        assert_eq!(json, r#"{"type":"normal","mean":1.0,"std_dev":2.0}"#);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::Uncertain;
-use rand_pcg::Pcg32;
+use crate::error::Interrupted;
+use crate::{context, Error, Options, Uncertain};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 const D0: f32 = 0.999;
 const D1: f32 = 0.999;
@@ -29,22 +31,63 @@ fn log_likelyhood_ratio(prob: f32, val: bool) -> f32 {
     reject_likelyhood(prob, val).ln() - accept_likelyhood(prob, val).ln()
 }
 
+/// The result of a test, see [`pr_with`](Uncertain::pr_with).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TestReport {
+    result: bool,
+    samples: usize,
+    successes: usize,
+}
+
+impl TestReport {
+    /// The outcome of the test, see [`pr`](Uncertain::pr).
+    pub fn result(&self) -> bool {
+        self.result
+    }
+
+    /// The number of samples which were taken before the test reached a conclusion.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// The fraction of samples which were `true`.
+    pub fn fraction(&self) -> f64 {
+        self.successes as f64 / self.samples as f64
+    }
+}
+
 /// Compute the sequential probability ration test.
-pub fn compute<U>(src: &U, prob: f32) -> bool
+pub fn compute<U>(src: &U, prob: f32, options: &Options) -> Result<TestReport, Error>
 where
     U: Uncertain + ?Sized,
     U::Value: Into<bool>,
 {
-    let mut rng = Pcg32::new(0xcafef00dd15ea5e5, 0xa02bdbf7bb3c0a7);
+    let _frame = context::isolate();
+    let mut rng = options.rng();
+    let budget = options.budget();
 
     let upper_ln = (D1 / (1.0 - D1)).ln();
     let lower_ln = ((1.0 - D0) / D0).ln();
     let mut ratio_ln = 0.0;
+    let mut successes = 0;
+    let mut samples = 0;
 
     for batch in 0..MAXS {
         for batch_step in 0..STEP {
             let epoch = STEP * batch + batch_step;
+            if let Err(limit) = budget.check(epoch) {
+                let estimate = successes as f64 / epoch as f64;
+                let std_error = (estimate * (1.0 - estimate) / epoch as f64).sqrt();
+                return Err(Error::Interrupted(Interrupted::new(
+                    limit,
+                    epoch,
+                    (epoch > 0).then_some((estimate, std_error)),
+                )));
+            }
             let val = src.sample(&mut rng, epoch).into();
+            successes += val as usize;
+            samples += 1;
             ratio_ln += log_likelyhood_ratio(prob, val);
         }
         if ratio_ln > upper_ln || ratio_ln < lower_ln {
@@ -52,7 +95,11 @@ where
         }
     }
 
-    ratio_ln < lower_ln
+    Ok(TestReport {
+        result: ratio_ln < lower_ln,
+        samples,
+        successes,
+    })
 }
 
 #[cfg(test)]
@@ -65,8 +112,35 @@ mod tests {
     fn basic_sprt_works() {
         let src = Distribution::from(Bernoulli::new(0.5).unwrap());
 
-        assert!(compute(&src, 0.4));
-        assert!(!compute(&src, 0.6));
+        assert!(compute(&src, 0.4, &Options::default()).unwrap().result());
+        assert!(!compute(&src, 0.6, &Options::default()).unwrap().result());
+
+        let report = compute(&src, 0.4, &Options::default()).unwrap();
+        assert_eq!(report.samples() % STEP, 0);
+        assert!((report.fraction() - 0.5).abs() < 0.2);
+    }
+
+    #[test]
+    fn budget_is_respected() {
+        let src = Distribution::from(Bernoulli::new(0.5).unwrap());
+        let options = Options::new().max_samples(25);
+        match compute(&src, 0.5, &options) {
+            Err(Error::Interrupted(partial)) => {
+                assert_eq!(partial.limit(), Limit::Samples);
+                assert_eq!(partial.samples(), 25);
+                assert!((0.0..=1.0).contains(&partial.estimate()));
+            }
+            _ => panic!("test should have been interrupted"),
+        }
+
+        let options = Options::new().max_samples(0);
+        match compute(&src, 0.5, &options) {
+            Err(Error::Interrupted(partial)) => {
+                assert!(partial.estimate().is_nan());
+                assert_eq!(partial.two_sigma_error(), f64::INFINITY);
+            }
+            _ => panic!("test should have been interrupted"),
+        }
     }
 
     #[test]
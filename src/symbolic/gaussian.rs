@@ -0,0 +1,158 @@
+//! Closed form evaluation of linear combinations of sources.
+
+use super::{Comparison, Expr};
+use crate::law::Law;
+use crate::math;
+
+/// An expression of the form `constant + sum(coefficient * source)`.
+struct Linear {
+    constant: f64,
+    terms: Vec<(usize, Law, f64)>,
+}
+
+impl Linear {
+    fn scale(mut self, factor: f64) -> Self {
+        self.constant *= factor;
+        for (_, _, coefficient) in self.terms.iter_mut() {
+            *coefficient *= factor;
+        }
+        self
+    }
+
+    fn combine(mut self, other: Self, sign: f64) -> Self {
+        self.constant += sign * other.constant;
+        for (id, law, coefficient) in other.terms {
+            match self.terms.iter_mut().find(|(other, _, _)| *other == id) {
+                Some((_, _, existing)) => *existing += sign * coefficient,
+                None => self.terms.push((id, law, sign * coefficient)),
+            }
+        }
+        self
+    }
+
+    fn constant(&self) -> Option<f64> {
+        if self.terms.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        self.terms
+            .iter()
+            .map(|(_, law, coefficient)| coefficient * law.moments().0)
+            .sum::<f64>()
+            + self.constant
+    }
+}
+
+fn linear(expr: &Expr) -> Option<Linear> {
+    match expr {
+        Expr::Source { id, law } => Some(Linear {
+            constant: 0.0,
+            terms: vec![(*id, *law, 1.0)],
+        }),
+        Expr::Constant(value) => Some(Linear {
+            constant: *value,
+            terms: Vec::new(),
+        }),
+        Expr::Sum(a, b) => Some(linear(a)?.combine(linear(b)?, 1.0)),
+        Expr::Difference(a, b) => Some(linear(a)?.combine(linear(b)?, -1.0)),
+        Expr::Product(a, b) => {
+            let (a, b) = (linear(a)?, linear(b)?);
+            match (a.constant(), b.constant()) {
+                (Some(factor), _) => Some(b.scale(factor)),
+                (_, Some(factor)) => Some(a.scale(factor)),
+                _ => None,
+            }
+        }
+        Expr::Ratio(a, b) => {
+            let divisor = linear(b)?.constant()?;
+            if divisor == 0.0 {
+                return None;
+            }
+            Some(linear(a)?.scale(1.0 / divisor))
+        }
+        Expr::Compare(..) | Expr::Not(_) | Expr::Function(..) | Expr::Opaque { .. } => None,
+    }
+}
+
+/// The exact mean of the expression, if it is a linear
+/// combination of sources.
+pub fn mean(expr: &Expr) -> Option<f64> {
+    linear(expr).map(|linear| linear.mean())
+}
+
+/// The exact probability of the expression being `true`, if it compares
+/// linear combinations of independent normal sources.
+pub fn probability(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Not(inner) => probability(inner).map(|p| 1.0 - p),
+        Expr::Compare(op, a, b) => {
+            // `a < b` is the event `b - a > 0`
+            let (a, b) = match op {
+                Comparison::Greater | Comparison::GreaterEqual => (a, b),
+                Comparison::Less | Comparison::LessEqual => (b, a),
+            };
+            let diff = linear(a)?.combine(linear(b)?, -1.0);
+            let mut mean = diff.constant;
+            let mut var = 0.0;
+            for (_, law, coefficient) in diff.terms.iter() {
+                let (mu, sigma) = law.normal()?;
+                mean += coefficient * mu;
+                var += (coefficient * sigma).powi(2);
+            }
+
+            let std_dev = var.sqrt();
+            if std_dev > 0.0 {
+                Some(math::normal_cdf(mean / std_dev))
+            } else {
+                let strict = matches!(op, Comparison::Less | Comparison::Greater);
+                if mean > 0.0 || (mean == 0.0 && !strict) {
+                    Some(1.0)
+                } else {
+                    Some(0.0)
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::law::Shape;
+
+    fn normal(id: usize, mean: f64, std_dev: f64) -> Expr {
+        Expr::Source {
+            id,
+            law: Law::new(Shape::Normal { mean, std_dev }),
+        }
+    }
+
+    #[test]
+    fn shared_sources_are_combined() {
+        // x - x is exactly zero
+        let diff = Expr::Difference(Box::new(normal(1, 3.0, 1.0)), Box::new(normal(1, 3.0, 1.0)));
+        let zero = Expr::Constant(0.0);
+        let ge = Expr::Compare(
+            Comparison::GreaterEqual,
+            Box::new(diff.clone()),
+            Box::new(zero.clone()),
+        );
+        let gt = Expr::Compare(Comparison::Greater, Box::new(diff), Box::new(zero));
+        assert_eq!(probability(&ge), Some(1.0));
+        assert_eq!(probability(&gt), Some(0.0));
+    }
+
+    #[test]
+    fn non_linear_expressions_are_rejected() {
+        let prod = Expr::Product(Box::new(normal(1, 0.0, 1.0)), Box::new(normal(2, 0.0, 1.0)));
+        assert!(mean(&prod).is_none());
+
+        let scaled = Expr::Ratio(Box::new(normal(1, 4.0, 1.0)), Box::new(Expr::Constant(2.0)));
+        assert_eq!(mean(&scaled), Some(2.0));
+    }
+}
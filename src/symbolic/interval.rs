@@ -0,0 +1,160 @@
+//! Guaranteed bounds using interval arithmetic.
+
+use super::{Expr, Function, UnsupportedError};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+type Interval = (f64, f64);
+
+/// Multiplication where `0 * inf = 0`, as required for bounds.
+fn mul(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+fn product((a, b): Interval, (c, d): Interval) -> Interval {
+    let corners = [mul(a, c), mul(a, d), mul(b, c), mul(b, d)];
+    let low = corners.iter().cloned().fold(f64::INFINITY, f64::min);
+    let high = corners.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    (low, high)
+}
+
+fn ratio(a: Interval, (c, d): Interval) -> Interval {
+    if c <= 0.0 && d >= 0.0 {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    } else {
+        product(a, (1.0 / d, 1.0 / c))
+    }
+}
+
+/// Bounds of `sin(x + shift)` for `x` in `[low, high]`.
+fn sine((low, high): Interval, shift: f64) -> Interval {
+    let (low, high) = (low + shift, high + shift);
+    if high - low >= 2.0 * PI || (high - low).is_nan() {
+        return (-1.0, 1.0);
+    }
+    // checks if `peak + 2 k pi` lies within the interval for some `k`
+    let contains = |peak: f64| {
+        let k = ((low - peak) / (2.0 * PI)).ceil();
+        peak + 2.0 * PI * k <= high
+    };
+    let (a, b) = (low.sin(), high.sin());
+    let min = if contains(-FRAC_PI_2) { -1.0 } else { a.min(b) };
+    let max = if contains(FRAC_PI_2) { 1.0 } else { a.max(b) };
+    (min, max)
+}
+
+fn function(func: Function, (low, high): Interval) -> Interval {
+    match func {
+        Function::Sqrt | Function::Exp | Function::Ln => {
+            let low = if func == Function::Exp {
+                low
+            } else {
+                low.max(0.0)
+            };
+            (func.apply(low), func.apply(high))
+        }
+        Function::Sin => sine((low, high), 0.0),
+        Function::Cos => sine((low, high), FRAC_PI_2),
+        Function::Abs => {
+            if low >= 0.0 {
+                (low, high)
+            } else if high <= 0.0 {
+                (-high, -low)
+            } else {
+                (0.0, high.max(-low))
+            }
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, unsupported: &mut Vec<&'static str>) -> Interval {
+    match expr {
+        Expr::Source { law, .. } => law.bounds(),
+        Expr::Constant(value) => (*value, *value),
+        Expr::Sum(a, b) => {
+            let (a, b) = (evaluate(a, unsupported), evaluate(b, unsupported));
+            (a.0 + b.0, a.1 + b.1)
+        }
+        Expr::Difference(a, b) => {
+            let (a, b) = (evaluate(a, unsupported), evaluate(b, unsupported));
+            (a.0 - b.1, a.1 - b.0)
+        }
+        Expr::Product(a, b) => product(evaluate(a, unsupported), evaluate(b, unsupported)),
+        Expr::Ratio(a, b) => ratio(evaluate(a, unsupported), evaluate(b, unsupported)),
+        Expr::Function(func, a) => function(*func, evaluate(a, unsupported)),
+        Expr::Compare(..) => {
+            unsupported.push("comparison");
+            (f64::NAN, f64::NAN)
+        }
+        Expr::Not(_) => {
+            unsupported.push("not");
+            (f64::NAN, f64::NAN)
+        }
+        Expr::Opaque { kind, .. } => {
+            unsupported.push(kind);
+            (f64::NAN, f64::NAN)
+        }
+    }
+}
+
+pub fn bounds(expr: &Expr) -> Result<Interval, UnsupportedError> {
+    let mut unsupported = Vec::new();
+    let bounds = evaluate(expr, &mut unsupported);
+    if unsupported.is_empty() {
+        Ok(bounds)
+    } else {
+        Err(UnsupportedError::new(unsupported))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Measurement, PointMass, Symbolic, Uncertain};
+
+    #[test]
+    fn arithmetic_bounds_are_tight() {
+        let x = Measurement::uniform(-1.0, 2.0).unwrap();
+        let y = Measurement::triangular(3.0, 3.5, 4.0).unwrap();
+        assert_eq!(x.mul(y).bounds(), Ok((-4.0, 8.0)));
+
+        let x = Measurement::uniform(1.0, 2.0).unwrap();
+        let y = Measurement::uniform(-1.0, 1.0).unwrap();
+        assert_eq!(PointMass::new(1.0).div(x).bounds(), Ok((0.5, 1.0)));
+        assert_eq!(
+            PointMass::new(1.0).div(y).bounds().unwrap().1,
+            f64::INFINITY
+        );
+
+        let z = Measurement::normal(0.0, 1.0)
+            .unwrap()
+            .truncate(-2.0, 1.0)
+            .unwrap();
+        assert_eq!(z.abs().bounds(), Ok((0.0, 2.0)));
+    }
+
+    #[test]
+    fn function_bounds_are_correct() {
+        assert_eq!(sine((0.0, 1.0), 0.0), (0.0, 1.0f64.sin()));
+        assert_eq!(sine((1.0, 2.0), 0.0), (1.0f64.sin(), 1.0));
+        assert_eq!(sine((-2.0, 2.0 * PI - 2.5), 0.0).0, -1.0);
+        assert_eq!(sine((0.0, 0.5), FRAC_PI_2), (0.5f64.cos(), 1.0));
+        assert_eq!(sine((0.0, f64::INFINITY), 0.0), (-1.0, 1.0));
+        assert_eq!(function(Function::Sqrt, (-1.0, 4.0)), (0.0, 2.0));
+    }
+
+    #[test]
+    fn unbounded_nodes_are_reported() {
+        let x = Measurement::uniform(0.0, 1.0).unwrap();
+        let y = Measurement::uniform(0.0, 1.0).unwrap().map(|v| v * 2.0);
+        let err = x.add(y).bounds().unwrap_err();
+        assert_eq!(err.nodes(), &["map"]);
+        assert_eq!(
+            Measurement::normal(0.0, 1.0).unwrap().bounds(),
+            Ok((f64::NEG_INFINITY, f64::INFINITY))
+        );
+    }
+}
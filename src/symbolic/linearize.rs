@@ -0,0 +1,209 @@
+//! First order propagation of uncertainties using dual numbers.
+
+use super::{Expr, UnsupportedError};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A number together with its partial derivatives with
+/// respect to every source it depends on.
+struct Dual {
+    value: f64,
+    grad: Vec<(usize, f64)>,
+}
+
+impl Dual {
+    fn constant(value: f64) -> Self {
+        Self {
+            value,
+            grad: Vec::new(),
+        }
+    }
+
+    /// Computes `self * scale + other * other_scale` for the gradient and
+    /// sets the value to `value`.
+    fn chain(mut self, scale: f64, other: Dual, other_scale: f64, value: f64) -> Self {
+        for (_, d) in self.grad.iter_mut() {
+            *d *= scale;
+        }
+        for (id, d) in other.grad {
+            match self.grad.iter_mut().find(|(other, _)| *other == id) {
+                Some((_, existing)) => *existing += other_scale * d,
+                None => self.grad.push((id, other_scale * d)),
+            }
+        }
+        self.value = value;
+        self
+    }
+}
+
+/// The result of propagating uncertainties using a first order
+/// Taylor expansion, see [`linearize`](super::Symbolic::linearize).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Estimate {
+    mean: f64,
+    uncertainty: f64,
+}
+
+impl Estimate {
+    /// The estimated mean, i.e. the result of the computation
+    /// evaluated at the mean of every source.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// The estimated standard uncertainty (standard deviation) of
+    /// the result.
+    pub fn uncertainty(&self) -> f64 {
+        self.uncertainty
+    }
+
+    /// The difference between `expectation` and the estimated mean,
+    /// in multiples of the estimated uncertainty.
+    ///
+    /// This can be used to check if the linear approximation is adequate
+    /// by passing the result of [`expect`](crate::Uncertain::expect).
+    /// Large values indicate that the computation is strongly non-linear over
+    /// the spread of its inputs.
+    pub fn discrepancy(&self, expectation: f64) -> f64 {
+        (expectation - self.mean) / self.uncertainty
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ± {}", self.mean, self.uncertainty)
+    }
+}
+
+fn evaluate(
+    expr: &Expr,
+    vars: &mut Vec<(usize, f64)>,
+    unsupported: &mut Vec<&'static str>,
+) -> Dual {
+    match expr {
+        Expr::Source { id, law } => {
+            let (mean, var) = law.moments();
+            if !vars.iter().any(|(other, _)| other == id) {
+                vars.push((*id, var));
+            }
+            Dual {
+                value: mean,
+                grad: vec![(*id, 1.0)],
+            }
+        }
+        Expr::Constant(value) => Dual::constant(*value),
+        Expr::Sum(a, b) => {
+            let (a, b) = (
+                evaluate(a, vars, unsupported),
+                evaluate(b, vars, unsupported),
+            );
+            let value = a.value + b.value;
+            a.chain(1.0, b, 1.0, value)
+        }
+        Expr::Difference(a, b) => {
+            let (a, b) = (
+                evaluate(a, vars, unsupported),
+                evaluate(b, vars, unsupported),
+            );
+            let value = a.value - b.value;
+            a.chain(1.0, b, -1.0, value)
+        }
+        Expr::Product(a, b) => {
+            let (a, b) = (
+                evaluate(a, vars, unsupported),
+                evaluate(b, vars, unsupported),
+            );
+            let (x, y) = (a.value, b.value);
+            a.chain(y, b, x, x * y)
+        }
+        Expr::Ratio(a, b) => {
+            let (a, b) = (
+                evaluate(a, vars, unsupported),
+                evaluate(b, vars, unsupported),
+            );
+            let (x, y) = (a.value, b.value);
+            a.chain(1.0 / y, b, -x / (y * y), x / y)
+        }
+        Expr::Function(func, a) => {
+            let a = evaluate(a, vars, unsupported);
+            let x = a.value;
+            a.chain(func.derivative(x), Dual::constant(0.0), 0.0, func.apply(x))
+        }
+        Expr::Compare(..) => {
+            unsupported.push("comparison");
+            Dual::constant(f64::NAN)
+        }
+        Expr::Not(_) => {
+            unsupported.push("not");
+            Dual::constant(f64::NAN)
+        }
+        Expr::Opaque { kind, .. } => {
+            unsupported.push(kind);
+            Dual::constant(f64::NAN)
+        }
+    }
+}
+
+pub fn estimate(expr: &Expr) -> Result<Estimate, UnsupportedError> {
+    let mut vars = Vec::new();
+    let mut unsupported = Vec::new();
+    let result = evaluate(expr, &mut vars, &mut unsupported);
+    if !unsupported.is_empty() {
+        return Err(UnsupportedError::new(unsupported));
+    }
+
+    let var: f64 = result
+        .grad
+        .iter()
+        .map(|(id, d)| {
+            let (_, var) = vars.iter().find(|(other, _)| other == id).unwrap();
+            d * d * var
+        })
+        .sum();
+    Ok(Estimate {
+        mean: result.value,
+        uncertainty: var.sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Distribution, Measurement, PointMass, Symbolic, Uncertain};
+    use rand_distr::Exp;
+
+    #[test]
+    fn shared_sources_are_correlated() {
+        let x = Measurement::normal(3.0, 0.5).unwrap().into_ref();
+
+        // d/dx (x * x - x) = 2x - 1
+        let y = (&x).mul(&x).sub(&x);
+        let estimate = y.linearize().unwrap();
+        assert_eq!(estimate.mean(), 6.0);
+        assert!((estimate.uncertainty() - 2.5).abs() < 1e-12);
+
+        let zero = (&x).sub(&x).linearize().unwrap();
+        assert_eq!(zero.uncertainty(), 0.0);
+    }
+
+    #[test]
+    fn functions_are_propagated() {
+        let x = Measurement::uniform(1.0, 2.0).unwrap();
+        let y = x.ln().mul(PointMass::new(2.0)).exp();
+        let estimate = y.linearize().unwrap();
+        assert!((estimate.mean() - 2.25).abs() < 1e-12);
+        assert!((estimate.uncertainty() - 3.0 / 12f64.sqrt()).abs() < 1e-12);
+
+        let expectation = y.expect(0.05).unwrap();
+        assert!(estimate.discrepancy(expectation).abs() < 0.2);
+    }
+
+    #[test]
+    fn unsupported_nodes_are_reported() {
+        let x = Distribution::from(Exp::new(1.0).unwrap());
+        let y = Measurement::normal(0.0, 1.0).unwrap().map(|v| v * 2.0);
+        let err = x.add(y).linearize().unwrap_err();
+        assert_eq!(err.nodes(), &["distribution", "map"]);
+    }
+}
@@ -0,0 +1,428 @@
+//! Exact computations on uncertain values which are known in closed form.
+
+use crate::law::Law;
+use crate::{ConvergenceError, Error, Uncertain};
+use num_traits::{identities, Float, NumCast};
+use std::fmt;
+
+mod gaussian;
+mod interval;
+mod linearize;
+
+pub use linearize::Estimate;
+
+/// A symbolic description of the computation graph of an uncertain value.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    /// A source with a distribution known in closed form. Sources
+    /// with the same `id` are the same (shared) value.
+    Source {
+        id: usize,
+        law: Law,
+    },
+    /// A fixed value.
+    Constant(f64),
+    Sum(Box<Expr>, Box<Expr>),
+    Difference(Box<Expr>, Box<Expr>),
+    Product(Box<Expr>, Box<Expr>),
+    Ratio(Box<Expr>, Box<Expr>),
+    /// Compares two values, `Compare(Greater, a, b)` is the event `a > b`.
+    Compare(Comparison, Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Function(Function, Box<Expr>),
+    /// A node which can not be described symbolically, e.g. because it
+    /// applies a closure.
+    Opaque {
+        id: usize,
+        kind: &'static str,
+    },
+}
+
+/// The direction of a comparison in an [`Expr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+/// A mathematical function in an [`Expr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sqrt,
+    Exp,
+    Ln,
+    Sin,
+    Cos,
+    Abs,
+}
+
+impl Function {
+    pub(crate) fn apply(&self, x: f64) -> f64 {
+        match self {
+            Function::Sqrt => x.sqrt(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Abs => x.abs(),
+        }
+    }
+
+    pub(crate) fn derivative(&self, x: f64) -> f64 {
+        match self {
+            Function::Sqrt => 0.5 / x.sqrt(),
+            Function::Exp => x.exp(),
+            Function::Ln => 1.0 / x,
+            Function::Sin => x.cos(),
+            Function::Cos => -x.sin(),
+            Function::Abs => x.signum(),
+        }
+    }
+}
+
+/// Error returned if an uncertain value can not be evaluated
+/// symbolically. Contains the kinds of nodes which are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedError {
+    nodes: Vec<&'static str>,
+}
+
+impl UnsupportedError {
+    pub(crate) fn new(nodes: Vec<&'static str>) -> Self {
+        Self { nodes }
+    }
+
+    /// The kinds of nodes in the computation which could not be
+    /// evaluated symbolically, e.g. `"map"`.
+    pub fn nodes(&self) -> &[&'static str] {
+        &self.nodes
+    }
+}
+
+impl fmt::Display for UnsupportedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unsupported nodes: {}", self.nodes.join(", "))
+    }
+}
+
+impl std::error::Error for UnsupportedError {}
+
+impl Expr {
+    pub(crate) fn opaque<T: ?Sized>(node: &T, kind: &'static str) -> Self {
+        Expr::Opaque {
+            id: node as *const T as *const u8 as usize,
+            kind,
+        }
+    }
+
+    pub(crate) fn source<T: ?Sized>(node: &T, law: Option<Law>, kind: &'static str) -> Self {
+        match law {
+            Some(law) => Expr::Source {
+                id: node as *const T as *const u8 as usize,
+                law,
+            },
+            None => Expr::opaque(node, kind),
+        }
+    }
+}
+
+/// Describes the computation graph of an uncertain value symbolically.
+///
+/// The trait is public, but lives in a private module, such that
+/// [`Symbolic`] can not be implemented outside of this crate.
+pub trait ToExpr {
+    /// Describe the computation graph of this uncertain value.
+    fn expr(&self) -> Expr;
+}
+
+/// Uncertain values which can be analysed symbolically.
+///
+/// When an uncertain value is computed from sources which are known in
+/// closed form (like [`Measurement`](crate::Measurement) or [`PointMass`](crate::PointMass)),
+/// some questions can be answered exactly, without sampling. This trait
+/// provides versions of [`pr`](Uncertain::pr) and [`expect`](Uncertain::expect)
+/// which make use of this where possible and fall back to sampling
+/// otherwise.
+///
+/// Currently, the following computations are tracked exactly:
+///
+/// - The expected value of sums, differences and scalar multiples
+///   of sources with known mean.
+/// - The probability of comparisons between sums, differences and scalar
+///   multiples of independent [normal][normal] sources.
+///
+/// Sources are known in closed form if they are created using
+/// [`Measurement`](crate::Measurement), or if they wrap a
+/// [`Distribution`](crate::Distribution) of a known family, like
+/// [`Normal`](rand_distr::Normal). Other sources are always sampled.
+///
+/// The queries of [`Uncertain`], like [`pr`](Uncertain::pr) and
+/// [`expect`](Uncertain::expect), always sample. They are available for
+/// every uncertain value, including those implemented outside of this
+/// crate, which can not describe their computation, and they report
+/// details of the sampling process, see [`Options`](crate::Options).
+///
+/// This trait is implemented for all uncertain values provided by this
+/// crate, except for [`BoxedUncertain`](crate::BoxedUncertain).
+///
+/// [normal]: https://en.wikipedia.org/wiki/Normal_distribution
+pub trait Symbolic: Uncertain + ToExpr {
+    /// Determine if the probability of obtaining `true` from this uncertain
+    /// value is at least `probability`.
+    ///
+    /// If the probability can be computed in closed form, the result is exact.
+    /// Otherwise, this is equivalent to [`pr`](Uncertain::pr).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability <= 0 || probability >= 1`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Symbolic, Measurement, PointMass};
+    ///
+    /// let x = Measurement::normal(5.0, 2.0).unwrap();
+    /// let y = Measurement::normal(7.0, 3.0).unwrap();
+    ///
+    /// // P(y - x > 0) = 0.71 (to two digits)
+    /// let is_larger = y.sub(x).gt(PointMass::new(0.0));
+    /// assert!(is_larger.analytic_pr(0.7));
+    /// assert!(!is_larger.analytic_pr(0.72));
+    /// ```
+    fn analytic_pr(&self, probability: f32) -> bool
+    where
+        Self::Value: Into<bool>,
+    {
+        match self.try_analytic_pr(probability) {
+            Ok(result) => result,
+            Err(_) => panic!("Probability {:?} must be in (0, 1)", probability),
+        }
+    }
+
+    /// Determine if the probability of obtaining `true` from this uncertain
+    /// value is at least `probability`. This is the same as
+    /// [`analytic_pr`](Self::analytic_pr), but returns an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidProbability`] if `probability <= 0 || probability >= 1`.
+    /// If the probability is not known in closed form, see [`try_pr`](Uncertain::try_pr).
+    fn try_analytic_pr(&self, probability: f32) -> Result<bool, Error>
+    where
+        Self::Value: Into<bool>,
+    {
+        if probability.is_nan() || probability <= 0.0 || probability >= 1.0 {
+            return Err(Error::InvalidProbability(probability));
+        }
+
+        match gaussian::probability(&self.expr()) {
+            Some(p) => Ok(p >= probability as f64),
+            None => self.try_pr(probability),
+        }
+    }
+
+    /// Calculate the expectation of this uncertain value.
+    ///
+    /// If the expectation can be computed in closed form, the result is exact.
+    /// Otherwise, this is equivalent to [`expect`](Uncertain::expect).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision <= 0`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Symbolic, Measurement, PointMass};
+    ///
+    /// let x = Measurement::uniform(0.0, 3.0).unwrap();
+    /// let y = Measurement::normal(7.0, 3.0).unwrap();
+    /// let z = x.add(y).mul(PointMass::new(2.0));
+    /// assert_eq!(z.analytic_expect(0.1).unwrap(), 17.0);
+    /// ```
+    fn analytic_expect(
+        &self,
+        precision: Self::Value,
+    ) -> Result<Self::Value, ConvergenceError<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        match self.try_analytic_expect(precision) {
+            Ok(value) => Ok(value),
+            Err(Error::NonConvergence(err)) => Err(err),
+            Err(Error::NonFinite { .. }) => Err(ConvergenceError::nan(precision)),
+            Err(_) => panic!("Precision must be larger than 0"),
+        }
+    }
+
+    /// Calculate the expectation of this uncertain value. This is the same
+    /// as [`analytic_expect`](Self::analytic_expect), but returns an error
+    /// instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPrecision`] if `precision <= 0`. If the
+    /// expectation is not known in closed form, see [`try_expect`](Uncertain::try_expect).
+    fn try_analytic_expect(&self, precision: Self::Value) -> Result<Self::Value, Error<Self::Value>>
+    where
+        Self::Value: Float,
+    {
+        if precision.is_nan() || precision <= identities::zero() {
+            return Err(Error::InvalidPrecision(precision));
+        }
+
+        match gaussian::mean(&self.expr()).and_then(<Self::Value as NumCast>::from) {
+            Some(mean) => Ok(mean),
+            None => self.try_expect(precision),
+        }
+    }
+
+    /// Propagate the mean and standard uncertainty of the sources through
+    /// the computation, using a first order Taylor expansion around the mean
+    /// of every source.
+    ///
+    /// This is the classic method for propagating measurement uncertainties
+    /// described in the [GUM][gum]. The result is deterministic and cheap to
+    /// compute, but only accurate if the computation is approximately linear
+    /// over the spread of its inputs. The estimate can be compared to the
+    /// result of [`expect`](Uncertain::expect) using [`Estimate::discrepancy`].
+    ///
+    /// Sources are assumed to be independent, unless they are shared
+    /// using [`into_ref`](Uncertain::into_ref).
+    ///
+    /// [gum]: https://www.bipm.org/en/committees/jc/jcgm/publications
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the computation contains nodes which can not be
+    /// differentiated, e.g. [`map`](Uncertain::map) or values wrapping a
+    /// [`Distribution`](crate::Distribution) of an unknown family.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage: the area of a rectangle.
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Symbolic, Measurement};
+    ///
+    /// let width = Measurement::normal(2.0, 0.1).unwrap();
+    /// let height = Measurement::normal(3.0, 0.1).unwrap();
+    /// let area = width.mul(height);
+    ///
+    /// let estimate = area.linearize().unwrap();
+    /// assert_eq!(estimate.mean(), 6.0);
+    /// assert!((estimate.uncertainty() - 0.13f64.sqrt()).abs() < 1e-12);
+    ///
+    /// let expectation = area.expect(0.01).unwrap();
+    /// assert!(estimate.discrepancy(expectation).abs() < 0.5);
+    /// ```
+    fn linearize(&self) -> Result<Estimate, UnsupportedError>
+    where
+        Self::Value: Float,
+    {
+        linearize::estimate(&self.expr())
+    }
+
+    /// Compute guaranteed bounds `(low, high)` on the values this uncertain
+    /// value can take, using [interval arithmetic][interval].
+    ///
+    /// Bounds are derived from the range of every source, e.g. the bounds of a
+    /// [uniform](crate::Measurement::uniform) measurement, a [`PointMass`](crate::PointMass)
+    /// or a [truncated](Uncertain::truncate) measurement. Unbounded sources,
+    /// like [normal](crate::Measurement::normal) measurements, result in infinite bounds.
+    ///
+    /// Every source is treated as varying independently, even if it is shared
+    /// using [`into_ref`](Uncertain::into_ref). The bounds are therefore always
+    /// valid, but may be wider than the true range of values.
+    ///
+    /// [interval]: https://en.wikipedia.org/wiki/Interval_arithmetic
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the computation contains nodes for which bounds can
+    /// not be derived, e.g. [`map`](Uncertain::map) or values wrapping a
+    /// [`Distribution`](crate::Distribution) of an unknown family.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage: a stack of three parts with a tolerance of `0.1` each.
+    ///
+    /// ```
+    /// use uncertain::{Uncertain, Symbolic, Measurement, Tolerance};
+    ///
+    /// let part = || Measurement::new(10.0, 0.1, Tolerance::Uniform).unwrap();
+    /// let stack = part().add(part()).add(part());
+    ///
+    /// let (low, high) = stack.bounds().unwrap();
+    /// assert!((low - 29.7).abs() < 1e-12);
+    /// assert!((high - 30.3).abs() < 1e-12);
+    /// ```
+    fn bounds(&self) -> Result<(f64, f64), UnsupportedError>
+    where
+        Self::Value: Float,
+    {
+        interval::bounds(&self.expr())
+    }
+}
+
+impl<U> Symbolic for U where U: Uncertain + ToExpr + ?Sized {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Distribution, Error, Measurement, PointMass, Symbolic, Uncertain};
+    use rand_distr::{Exp, Normal};
+
+    #[test]
+    fn linear_gaussians_are_exact() {
+        let x = Measurement::normal(1.0, 1.0).unwrap().into_ref();
+        let y = Measurement::normal(-1.0, 2.0).unwrap();
+
+        // 2x + y - x ~ N(0, sqrt(5))
+        let z = (&x).mul(PointMass::new(2.0)).add(y).sub(&x);
+        assert_eq!(z.analytic_expect(1e-6).unwrap(), 0.0);
+
+        // P(z < 1) = 0.6726 (to four digits)
+        let below = z.lt(PointMass::new(1.0));
+        assert!(below.analytic_pr(0.672));
+        assert!(!below.analytic_pr(0.673));
+        assert!(below.not().analytic_pr(0.327));
+    }
+
+    #[test]
+    fn normal_distributions_are_exact() {
+        let x = Distribution::from(Normal::new(5.0, 2.0).unwrap());
+        let y = Distribution::from(Normal::new(7.0, 3.0).unwrap());
+        let z = x.add(y);
+        assert_eq!(z.try_analytic_expect(1e-6).unwrap(), 12.0);
+
+        // P(x + y > 13) = 0.3908 (to four digits)
+        let above = z.gt(PointMass::new(13.0));
+        assert!(above.try_analytic_pr(0.390).unwrap());
+        assert!(!above.try_analytic_pr(0.391).unwrap());
+        assert!(matches!(
+            above.try_analytic_pr(1.5),
+            Err(Error::InvalidProbability(_))
+        ));
+    }
+
+    #[test]
+    fn non_gaussian_graphs_fall_back_to_sampling() {
+        let x = Distribution::from(Exp::new(1.0).unwrap());
+        let y = Measurement::uniform(0.0, 2.0).unwrap();
+        let z = x.add(y);
+        assert!((z.analytic_expect(0.1).unwrap() - 2.0).abs() < 0.1);
+        assert!(matches!(
+            z.try_analytic_expect(0.0),
+            Err(Error::InvalidPrecision(_))
+        ));
+        assert!(z.gt(PointMass::new(0.0)).analytic_pr(0.9));
+    }
+}
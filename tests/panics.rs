@@ -1,4 +1,4 @@
-use uncertain::{PointMass, Uncertain};
+use uncertain::{Error, PointMass, Uncertain};
 
 #[test]
 #[should_panic]
@@ -20,3 +20,17 @@ fn test_nagative_expect_precision_panics() {
     let x = PointMass::new(0.0);
     x.expect(-0.1).ok();
 }
+
+#[test]
+fn test_fallible_queries_do_not_panic() {
+    let x = PointMass::new(true);
+    assert!(matches!(x.try_pr(1.2), Err(Error::InvalidProbability(_))));
+    assert!(matches!(x.try_pr(-0.3), Err(Error::InvalidProbability(_))));
+
+    let x = PointMass::new(0.0);
+    assert!(matches!(
+        x.try_expect(-0.1),
+        Err(Error::InvalidPrecision(_))
+    ));
+    assert!(matches!(x.try_expect(0.0), Err(Error::InvalidPrecision(_))));
+}